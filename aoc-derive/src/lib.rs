@@ -0,0 +1,339 @@
+//! Derive and attribute macros for the `advent-of-code-rust` crate.
+//!
+//! The generated code refers to `crate::aoc`, so these macros can only be
+//! used inside that crate.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{quote, ToTokens};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Expr, ExprLit, Fields, FnArg, GenericArgument, Ident, ItemFn,
+    Lifetime, Lit, LitInt, LitStr, MetaNameValue, PathArguments, ReturnType, Token, Type,
+};
+
+/// Parses a line into a struct following a template given in a `line`
+/// attribute:
+///
+/// ```ignore
+/// #[derive(FromLine)]
+/// #[line("{from} to {to} = {distance}")]
+/// struct Distance {
+///     from: String,
+///     to: String,
+///     distance: u32,
+/// }
+/// ```
+///
+/// Every field must appear once in the template, and its type must
+/// implement `aoc::parse::Token`. Words in the template must match whole
+/// words in the line, any other text must match exactly, and any amount of
+/// space is allowed wherever the template has some.
+///
+/// This implements `aoc::parse::FromLine`, and also `FromStr` for structs
+/// that do not borrow from the line.
+#[proc_macro_derive(FromLine, attributes(line))]
+pub fn derive_from_line(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    from_line(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+enum Piece {
+    Keyword(String),
+    Literal(String),
+    Field(String),
+}
+
+fn from_line(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(name, "FromLine needs a struct with named fields")),
+        },
+        _ => return Err(Error::new_spanned(name, "FromLine can only be derived for structs")),
+    };
+
+    let template = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("line"))
+        .ok_or_else(|| Error::new_spanned(name, "missing #[line(\"...\")] template"))?
+        .parse_args::<LitStr>()?;
+
+    let pieces = pieces(&template.value()).map_err(|message| Error::new_spanned(&template, message))?;
+
+    let mut lifetimes = input.generics.lifetimes();
+    let (lifetime, borrowed) = match (lifetimes.next(), lifetimes.next()) {
+        (None, _) => (Lifetime::new("'line", Span::call_site()), false),
+        (Some(def), None) => (def.lifetime.clone(), true),
+        (Some(_), Some(extra)) => return Err(Error::new_spanned(extra, "FromLine supports at most one lifetime")),
+    };
+    if input.generics.type_params().next().is_some() || input.generics.const_params().next().is_some() {
+        return Err(Error::new_spanned(&input.generics, "FromLine does not support type parameters"));
+    }
+
+    let mut reads = vec![];
+    let mut used = vec![];
+    for piece in &pieces {
+        reads.push(match piece {
+            Piece::Keyword(word) => quote! { c.keyword(#word)?; },
+            Piece::Literal(text) => quote! { c.literal(#text)?; },
+            Piece::Field(field) => {
+                let found = fields
+                    .iter()
+                    .find(|f| f.ident.as_ref().is_some_and(|i| i == field))
+                    .ok_or_else(|| Error::new_spanned(&template, format!("`{}` is not a field of `{}`", field, name)))?;
+                if used.contains(field) {
+                    return Err(Error::new_spanned(&template, format!("`{}` appears more than once", field)));
+                }
+                used.push(field.clone());
+
+                let ident = Ident::new(field, Span::call_site());
+                let ty = &found.ty;
+                quote! { let #ident = <#ty as crate::aoc::parse::Token<#lifetime>>::read(c)?; }
+            }
+        });
+    }
+
+    let idents = fields.iter().map(|f| f.ident.clone().unwrap()).collect::<Vec<_>>();
+    if let Some(missing) = idents.iter().find(|i| !used.contains(&i.to_string())) {
+        return Err(Error::new_spanned(missing, format!("`{}` does not appear in the template", missing)));
+    }
+
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let impl_generics = if borrowed { quote! { #impl_generics } } else { quote! { <#lifetime> } };
+
+    let from_str = if borrowed {
+        quote! {}
+    } else {
+        quote! {
+            impl std::str::FromStr for #name {
+                type Err = crate::aoc::Error;
+
+                fn from_str(s: &str) -> crate::aoc::Result<Self> {
+                    let mut c = crate::aoc::parse::Cursor::new(s, s);
+                    let value = <Self as crate::aoc::parse::FromLine>::from_line(&mut c)?;
+                    c.end()?;
+
+                    Ok(value)
+                }
+            }
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics crate::aoc::parse::FromLine<#lifetime> for #name #ty_generics {
+            fn from_line(c: &mut crate::aoc::parse::Cursor<#lifetime>) -> crate::aoc::Result<Self> {
+                #(#reads)*
+
+                Ok(#name { #(#idents),* })
+            }
+        }
+
+        #from_str
+    })
+}
+
+// Splits a template into placeholders and the text between them. Text made
+// of word characters and followed by a space, or by the end of the
+// template, is a keyword so that it does not match the start of a longer
+// word.
+fn pieces(template: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = vec![];
+    let mut rest = template;
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('{') {
+            let end = after.find('}').ok_or("unclosed `{` in template")?;
+            let field = after[..end].trim();
+            if field.is_empty() {
+                return Err("empty `{}` in template".to_owned());
+            }
+            pieces.push(Piece::Field(field.to_owned()));
+            rest = &after[end + 1..];
+            continue;
+        }
+
+        let rest_trimmed = rest.trim_start();
+        if rest_trimmed.len() != rest.len() {
+            rest = rest_trimmed;
+            continue;
+        }
+
+        let end = rest.find(|c: char| c.is_whitespace() || c == '{').unwrap_or(rest.len());
+        let text = &rest[..end];
+        rest = &rest[end..];
+
+        let is_word = text.chars().all(|c| c.is_alphanumeric() || c == '_');
+        if is_word && !rest.starts_with('{') {
+            pieces.push(Piece::Keyword(text.to_owned()));
+        } else {
+            pieces.push(Piece::Literal(text.to_owned()));
+        }
+    }
+
+    Ok(pieces)
+}
+
+/// Registers a function as the solution of one part of a day:
+///
+/// ```ignore
+/// #[aoc(year = 2015, day = 10, part = 1)]
+/// fn part1(input: &str) -> Result<u32> { ... }
+/// ```
+///
+/// The function takes either the input as `&str`, or a reference to what
+/// the `#[aoc_parser]` of the same day returns. Its result can be anything
+/// that implements `Display`. See `aoc::registry`.
+#[proc_macro_attribute]
+pub fn aoc(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args with Punctuated::<MetaNameValue, Token![,]>::parse_terminated);
+    let item = parse_macro_input!(item as ItemFn);
+
+    register(args, item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Declares the function that parses the input of a day for its `#[aoc]`
+/// functions:
+///
+/// ```ignore
+/// #[aoc_parser(year = 2015, day = 2)]
+/// fn parse_input(input: &str) -> Result<Vec<(u32, u32, u32)>> { ... }
+/// ```
+///
+/// It must return `Result<T>`, where `T` does not borrow from the input.
+#[proc_macro_attribute]
+pub fn aoc_parser(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args with Punctuated::<MetaNameValue, Token![,]>::parse_terminated);
+    let item = parse_macro_input!(item as ItemFn);
+
+    parser(args, item)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+type Args = Punctuated<MetaNameValue, Token![,]>;
+
+// The integer given to `name`, which must be in `range`.
+fn int_arg(
+    args: &Args,
+    name: &str,
+    range: std::ops::RangeInclusive<u16>,
+    span: &impl ToTokens,
+) -> syn::Result<LitInt> {
+    let arg = args
+        .iter()
+        .find(|arg| arg.path.is_ident(name))
+        .ok_or_else(|| Error::new_spanned(span, format!("missing `{} = ...`", name)))?;
+
+    let value = match &arg.value {
+        Expr::Lit(ExprLit { lit: Lit::Int(value), .. }) => value,
+        other => return Err(Error::new_spanned(other, format!("`{}` must be an integer", name))),
+    };
+    if !range.contains(&value.base10_parse::<u16>()?) {
+        let message = format!("`{}` must be between {} and {}", name, range.start(), range.end());
+        return Err(Error::new_spanned(value, message));
+    }
+
+    Ok(LitInt::new(value.base10_digits(), value.span()))
+}
+
+fn check_args(args: &Args, allowed: &[&str]) -> syn::Result<()> {
+    match args.iter().find(|arg| !allowed.iter().any(|name| arg.path.is_ident(name))) {
+        Some(arg) => Err(Error::new_spanned(&arg.path, format!("expected one of: {}", allowed.join(", ")))),
+        None => Ok(()),
+    }
+}
+
+fn single_input(item: &ItemFn) -> syn::Result<&Type> {
+    let inputs = &item.sig.inputs;
+    match (inputs.first(), inputs.len()) {
+        (Some(FnArg::Typed(arg)), 1) => Ok(&arg.ty),
+        _ => Err(Error::new_spanned(&item.sig, "expected a function with a single argument")),
+    }
+}
+
+fn register(args: Args, item: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    check_args(&args, &["year", "day", "part"])?;
+    let year = int_arg(&args, "year", 2015..=9999, &item.sig.ident)?;
+    let day = int_arg(&args, "day", 1..=25, &item.sig.ident)?;
+    let part = int_arg(&args, "part", 1..=2, &item.sig.ident)?;
+
+    let name = &item.sig.ident;
+    let takes_text = match single_input(&item)? {
+        Type::Reference(reference) => matches!(&*reference.elem, Type::Path(path) if path.path.is_ident("str")),
+        _ => false,
+    };
+
+    let call = if takes_text {
+        quote! { #name(input)? }
+    } else {
+        quote! {
+            #name(&<crate::aoc::registry::Input as crate::aoc::registry::Parser<#year, #day>>::parse(input)?)?
+        }
+    };
+
+    Ok(quote! {
+        #item
+
+        const _: () = {
+            fn solve(input: &str) -> crate::aoc::Result<Box<dyn std::fmt::Display>> {
+                Ok(Box::new(#call))
+            }
+
+            crate::aoc::registry::inventory::submit! {
+                crate::aoc::registry::Entry { year: #year, day: #day, part: #part, solve }
+            }
+        };
+    })
+}
+
+fn parser(args: Args, item: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    check_args(&args, &["year", "day"])?;
+    let year = int_arg(&args, "year", 2015..=9999, &item.sig.ident)?;
+    let day = int_arg(&args, "day", 1..=25, &item.sig.ident)?;
+    single_input(&item)?;
+
+    let name = &item.sig.ident;
+    let output = result_type(&item.sig.output)
+        .ok_or_else(|| Error::new_spanned(&item.sig, "a parser must return `Result<T>`"))?;
+
+    Ok(quote! {
+        #item
+
+        impl crate::aoc::registry::Parser<#year, #day> for crate::aoc::registry::Input {
+            type Output = #output;
+
+            fn parse(input: &str) -> crate::aoc::Result<#output> {
+                #name(input)
+            }
+        }
+    })
+}
+
+// The `T` of a `Result<T>` return type.
+fn result_type(output: &ReturnType) -> Option<&Type> {
+    let path = match output {
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Path(path) => &path.path,
+            _ => return None,
+        },
+        ReturnType::Default => return None,
+    };
+
+    let last = path.segments.last().filter(|s| s.ident == "Result")?;
+    match &last.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(GenericArgument::Type(ty)) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
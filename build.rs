@@ -0,0 +1,64 @@
+//! Writes the list of embedded puzzle inputs to `$OUT_DIR/inputs.rs`. It is
+//! only filled in with the `embed-inputs` feature, from the files found
+//! under `inputs/<year>/<day>.txt`, with days always written with two
+//! digits, like `01`, so that each day has a single file.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let mut inputs = vec![];
+    if env::var_os("CARGO_FEATURE_EMBED_INPUTS").is_some() {
+        let dir = Path::new(&env::var_os("CARGO_MANIFEST_DIR").unwrap()).join("inputs");
+        println!("cargo:rerun-if-changed={}", dir.display());
+        inputs = find_inputs(&dir);
+    }
+
+    let mut code = String::from("const EMBEDDED_INPUTS: &[(u16, u8, &str)] = &[\n");
+    for (year, day, path) in inputs {
+        code.push_str(&format!("    ({}, {}, include_str!({:?})),\n", year, day, path.display()));
+    }
+    code.push_str("];\n");
+
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("inputs.rs");
+    fs::write(out, code).unwrap();
+}
+
+// Files and directories with other names are left alone.
+fn find_inputs(dir: &Path) -> Vec<(u16, u8, PathBuf)> {
+    let mut inputs = vec![];
+    for year in entries(dir) {
+        let Some(number) = year.file_name().and_then(|n| digits(n.to_str()?, 4)) else { continue };
+
+        for file in entries(&year) {
+            let day = file
+                .file_name()
+                .and_then(|n| digits(n.to_str()?.strip_suffix(".txt")?, 2))
+                .and_then(|day| u8::try_from(day).ok())
+                .filter(|day| (1..=25).contains(day));
+
+            if let Some(day) = day {
+                inputs.push((number, day, file));
+            }
+        }
+    }
+
+    inputs.sort();
+    inputs
+}
+
+// Only names of exactly `count` digits, so that no two names give the same
+// number.
+fn digits(name: &str, count: usize) -> Option<u16> {
+    (name.len() == count && name.bytes().all(|b| b.is_ascii_digit())).then(|| name.parse().ok())?
+}
+
+fn entries(dir: &Path) -> Vec<PathBuf> {
+    match fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| Some(e.ok()?.path())).collect(),
+        Err(_) => vec![],
+    }
+}
@@ -0,0 +1,13 @@
+#![no_main]
+
+use advent_of_code_rust::aoc::registry::Registered;
+use advent_of_code_rust::aoc::DaySolution;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let solution = Registered::new(2015, 2);
+        let _ = solution.solve_part1(input);
+        let _ = solution.solve_part2(input);
+    }
+});
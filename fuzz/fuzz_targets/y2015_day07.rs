@@ -0,0 +1,12 @@
+#![no_main]
+
+use advent_of_code_rust::aoc::y2015::day07::Solution;
+use advent_of_code_rust::aoc::DaySolution;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = Solution.solve_part1(input);
+        let _ = Solution.solve_part2(input);
+    }
+});
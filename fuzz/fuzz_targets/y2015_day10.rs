@@ -0,0 +1,21 @@
+#![no_main]
+
+use advent_of_code_rust::aoc::registry::Registered;
+use advent_of_code_rust::aoc::DaySolution;
+use libfuzzer_sys::fuzz_target;
+
+// Each of the 50 rounds makes the sequence about 30% longer, so only
+// inputs as short as the real ones finish in reasonable time and memory.
+const MAX_LEN: usize = 10;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() > MAX_LEN {
+        return;
+    }
+
+    if let Ok(input) = std::str::from_utf8(data) {
+        let solution = Registered::new(2015, 10);
+        let _ = solution.solve_part1(input);
+        let _ = solution.solve_part2(input);
+    }
+});
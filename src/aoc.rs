@@ -1,15 +1,47 @@
+pub mod bitset;
+pub mod combinatorics;
+pub mod crosscheck;
+pub mod debug;
+pub mod diagnostic;
+pub mod generator;
+pub mod geom;
+pub mod graph;
+pub mod grid;
+pub mod interval;
+pub mod math;
+pub mod memo;
+pub mod panic;
+pub mod parse;
+pub mod registry;
+pub mod rng;
+pub mod search;
+pub mod space;
+pub mod stream;
+pub mod trace;
+pub mod viz;
+#[cfg(feature = "y2015")]
 pub mod y2015;
 
 use std::fmt;
 use std::error::Error as StdError;
 
+pub use diagnostic::ParseError;
+
 pub enum Error {
     InvalidInput,
+    Parse(ParseError),
+    Multiple(Vec<Error>),
     ResultNotFound,
+    /// Keys that depend on each other, starting and ending with the same one.
+    Cycle(Vec<String>),
     GenericError{
         message: String,
         source: Box<dyn StdError>,
     },
+    Panic{
+        message: String,
+        location: Option<String>,
+    },
 }
 
 impl fmt::Display for Error {
@@ -17,10 +49,25 @@ impl fmt::Display for Error {
         match self {
             Error::InvalidInput =>
                 write!(f, "invalid input"),
+            Error::Parse(e) =>
+                write!(f, "invalid input at {}", e),
+            Error::Multiple(errors) => {
+                write!(f, "{} errors found", errors.len())?;
+                for e in errors {
+                    write!(f, "\n\n{}", e)?;
+                }
+                Ok(())
+            },
             Error::ResultNotFound =>
                 write!(f, "result not found"),
+            Error::Cycle(keys) =>
+                write!(f, "the input has a cycle: {}", keys.join(" -> ")),
             Error::GenericError{message, ..} =>
                 write!(f, "an unexpected error ocurred: {}", message),
+            Error::Panic{message, location: Some(location)} =>
+                write!(f, "panicked at {}: {}", location, message),
+            Error::Panic{message, location: None} =>
+                write!(f, "panicked: {}", message),
         }
     }
 }
@@ -29,26 +76,69 @@ impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::InvalidInput =>
-                write!(f, "invalid input"),
+                write!(f, "InvalidInput"),
+            Error::Parse(e) =>
+                f.debug_tuple("Parse").field(e).finish(),
+            Error::Multiple(errors) =>
+                f.debug_tuple("Multiple").field(errors).finish(),
             Error::ResultNotFound =>
-                write!(f, "result not found"),
-            Error::GenericError{message, ..} =>
-                write!(f, "an unexpected error ocurred: {}", message),
+                write!(f, "ResultNotFound"),
+            Error::Cycle(keys) =>
+                f.debug_tuple("Cycle").field(keys).finish(),
+            Error::GenericError{message, source} =>
+                f.debug_struct("GenericError")
+                    .field("message", message)
+                    .field("source", source)
+                    .finish(),
+            Error::Panic{message, location} =>
+                f.debug_struct("Panic")
+                    .field("message", message)
+                    .field("location", location)
+                    .finish(),
         }
     }
 }
 
+impl Error {
+    /// A parse error pointing at `span`, which must be a slice of `input`.
+    pub fn parse(input: &str, span: &str, expected: impl Into<String>) -> Self {
+        Error::Parse(ParseError::at(input, span, expected))
+    }
+}
+
 impl StdError for Error {}
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Returns every error in `results` instead of stopping at the first one.
+pub fn all_errors<T>(results: impl IntoIterator<Item = Result<T>>) -> Result<()> {
+    let mut errors = results
+        .into_iter()
+        .filter_map(|r| r.err())
+        .collect::<Vec<_>>();
+
+    match errors.len() {
+        0 => Ok(()),
+        1 => Err(errors.remove(0)),
+        _ => Err(Error::Multiple(errors)),
+    }
+}
+
 pub trait DaySolution<T> {
     fn solve_part1(&self, input: &str) -> Result<T>;
 
     fn solve_part2(&self, input: &str) -> Result<T>;
 }
 
+pub trait Validate {
+    /// Parses the whole input without solving it, reporting every malformed
+    /// line instead of only the first one.
+    fn validate(&self, input: &str) -> Result<()>;
+}
+
+// the helpers are for the days, a build without any year does not use them
 #[cfg(test)]
+#[cfg_attr(not(feature = "y2015"), allow(dead_code))]
 mod test {
     use super::*;
 
@@ -57,7 +147,7 @@ mod test {
         Two,
     }
 
-    pub fn test_case<T: fmt::Display>(part: Part, solution: impl DaySolution<T>, input: &str, expected: T) {
+    pub fn test_case<T: fmt::Display>(part: Part, solution: impl DaySolution<T>, input: &str, expected: impl fmt::Display) {
         let result = match part {
             Part::One => solution.solve_part1(input),
             Part::Two => solution.solve_part2(input),
@@ -76,4 +166,102 @@ mod test {
             got,
             expected);
     }
+
+    /// Checks that a part rejects the input with a parse error at the given
+    /// line and column.
+    pub fn parse_error_case<T: fmt::Debug>(part: Part, solution: impl DaySolution<T>, input: &str, line: usize, column: usize) {
+        let result = match part {
+            Part::One => solution.solve_part1(input),
+            Part::Two => solution.solve_part2(input),
+        };
+
+        match result {
+            Err(Error::Parse(e)) =>
+                assert_eq!((e.line, e.column), (line, column), "input '{}', got: {}", input, e),
+            other =>
+                panic!("input '{}', expected a parse error, got: {:?}", input, other),
+        }
+    }
+
+    /// Checks that validating the input finds errors in exactly these lines.
+    pub fn validate_case(solution: impl Validate, input: &str, lines: &[usize]) {
+        let errors = match solution.validate(input) {
+            Ok(()) => vec![],
+            Err(Error::Multiple(errors)) => errors,
+            Err(e) => vec![e],
+        };
+
+        let got = errors
+            .iter()
+            .map(|e| match e {
+                Error::Parse(e) => e.line,
+                other => panic!("input '{}', expected a parse error, got: {:?}", input, other),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines, got, "input '{}'", input);
+    }
+
+    /// Runs both parts on every input of a fuzz target corpus. Any result is
+    /// fine, as long as it does not panic.
+    pub fn fuzz_corpus_case<T>(solution: impl DaySolution<T>, target: &str) {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fuzz")
+            .join("corpus")
+            .join(target);
+
+        for entry in std::fs::read_dir(&dir).unwrap() {
+            let data = std::fs::read(entry.unwrap().path()).unwrap();
+            if let Ok(input) = std::str::from_utf8(&data) {
+                let _ = solution.solve_part1(input);
+                let _ = solution.solve_part2(input);
+            }
+        }
+    }
+
+    /// Checks that streaming a part gives the same answer as solving it from
+    /// a string, or fails with a parse error at the same place.
+    pub fn stream_case<T, S>(part: Part, solution: S, input: &str)
+    where
+        T: PartialEq + fmt::Debug,
+        S: DaySolution<T> + stream::StreamingSolution<T>,
+    {
+        let mut reader = input.as_bytes();
+        let (expected, got) = match part {
+            Part::One => (solution.solve_part1(input), solution.stream_part1(&mut reader)),
+            Part::Two => (solution.solve_part2(input), solution.stream_part2(&mut reader)),
+        };
+
+        match (expected, got) {
+            (Ok(expected), Ok(got)) => assert_eq!(expected, got, "input '{}'", input),
+            (Err(Error::Parse(expected)), Err(Error::Parse(got))) =>
+                assert_eq!((expected.line, expected.column), (got.line, got.column), "input '{}'", input),
+            (Err(expected), Err(got)) =>
+                assert_eq!(expected.to_string(), got.to_string(), "input '{}'", input),
+            (expected, got) =>
+                panic!("input '{}', expected {:?}, got {:?}", input, expected, got),
+        }
+    }
+
+    /// Checks that inputs generated for a day are accepted by its solution.
+    pub fn generated_case<T, S>(part: Part, solution: S, size: usize)
+    where
+        S: DaySolution<T> + generator::InputGenerator,
+    {
+        for seed in 0..5 {
+            let input = solution.generate(&mut rng::Rng::new(seed), size);
+            let result = match part {
+                Part::One => solution.solve_part1(&input),
+                Part::Two => solution.solve_part2(&input),
+            };
+
+            assert!(
+                result.is_ok(),
+                "seed {}, size {}: got '{}' for input '{}'",
+                seed,
+                size,
+                result.err().unwrap(),
+                input);
+        }
+    }
 }
@@ -0,0 +1,326 @@
+//! Sets of bits packed 64 to a word, for boolean grids and sets of small
+//! integers. Ranges and set operations work a word at a time.
+//!
+//! [`FixedBitSet`] has a length given up front and panics on bits past it,
+//! like a slice. [`BitSet`] grows when a bit past its end is set.
+
+use std::ops::Range;
+
+const BITS: usize = u64::BITS as usize;
+
+// Calls `op` with each word covering `range` and the mask of the bits of
+// the range in that word.
+fn for_each_word(words: &mut [u64], range: Range<usize>, op: impl Fn(&mut u64, u64)) {
+    if range.is_empty() {
+        return;
+    }
+
+    let (first, last) = (range.start / BITS, (range.end - 1) / BITS);
+    for (i, word) in words.iter_mut().enumerate().take(last + 1).skip(first) {
+        let low = if i == first { range.start % BITS } else { 0 };
+        let high = if i == last { (range.end - 1) % BITS } else { BITS - 1 };
+        op(word, (u64::MAX << low) & (u64::MAX >> (BITS - 1 - high)));
+    }
+}
+
+fn count_ones(words: &[u64]) -> usize {
+    words.iter().map(|w| w.count_ones() as usize).sum()
+}
+
+fn ones(words: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    words.iter().enumerate().flat_map(|(i, &word)| {
+        let mut rest = word;
+        std::iter::from_fn(move || {
+            if rest == 0 {
+                return None;
+            }
+
+            let bit = rest.trailing_zeros() as usize;
+            rest &= rest - 1;
+            Some(i * BITS + bit)
+        })
+    })
+}
+
+/// A set of bits numbered from 0 to `len - 1`, all clear at first.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FixedBitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl FixedBitSet {
+    pub fn new(len: usize) -> Self {
+        FixedBitSet { words: vec![0; len.div_ceil(BITS)], len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    fn check(&self, range: &Range<usize>) {
+        assert!(
+            range.start <= range.end && range.end <= self.len,
+            "bits {:?} are outside a set of {} bits",
+            range,
+            self.len);
+    }
+
+    pub fn get(&self, bit: usize) -> bool {
+        self.check(&(bit..bit + 1));
+        self.words[bit / BITS] & (1 << (bit % BITS)) != 0
+    }
+
+    pub fn set(&mut self, bit: usize) {
+        self.set_range(bit..bit + 1);
+    }
+
+    pub fn clear(&mut self, bit: usize) {
+        self.clear_range(bit..bit + 1);
+    }
+
+    pub fn toggle(&mut self, bit: usize) {
+        self.toggle_range(bit..bit + 1);
+    }
+
+    pub fn set_range(&mut self, range: Range<usize>) {
+        self.check(&range);
+        for_each_word(&mut self.words, range, |word, mask| *word |= mask);
+    }
+
+    pub fn clear_range(&mut self, range: Range<usize>) {
+        self.check(&range);
+        for_each_word(&mut self.words, range, |word, mask| *word &= !mask);
+    }
+
+    pub fn toggle_range(&mut self, range: Range<usize>) {
+        self.check(&range);
+        for_each_word(&mut self.words, range, |word, mask| *word ^= mask);
+    }
+
+    /// The number of set bits.
+    pub fn count_ones(&self) -> usize {
+        count_ones(&self.words)
+    }
+
+    /// The set bits, in increasing order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        ones(&self.words)
+    }
+
+    fn combine(&mut self, other: &FixedBitSet, op: impl Fn(u64, u64) -> u64) {
+        assert_eq!(self.len, other.len, "sets of bits of different lengths");
+        for (word, &other) in self.words.iter_mut().zip(&other.words) {
+            *word = op(*word, other);
+        }
+    }
+
+    pub fn union_with(&mut self, other: &FixedBitSet) {
+        self.combine(other, |a, b| a | b);
+    }
+
+    pub fn intersect_with(&mut self, other: &FixedBitSet) {
+        self.combine(other, |a, b| a & b);
+    }
+
+    pub fn difference_with(&mut self, other: &FixedBitSet) {
+        self.combine(other, |a, b| a & !b);
+    }
+
+    pub fn symmetric_difference_with(&mut self, other: &FixedBitSet) {
+        self.combine(other, |a, b| a ^ b);
+    }
+}
+
+/// A set of small integers, taking a bit for every integer up to the
+/// biggest one ever in it.
+#[derive(Clone, Default, Debug)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    fn grow(&mut self, bits: usize) {
+        let words = bits.div_ceil(BITS);
+        if words > self.words.len() {
+            self.words.resize(words, 0);
+        }
+    }
+
+    pub fn get(&self, bit: usize) -> bool {
+        self.words.get(bit / BITS).is_some_and(|word| word & (1 << (bit % BITS)) != 0)
+    }
+
+    pub fn set(&mut self, bit: usize) {
+        self.set_range(bit..bit + 1);
+    }
+
+    pub fn clear(&mut self, bit: usize) {
+        self.clear_range(bit..bit + 1);
+    }
+
+    pub fn toggle(&mut self, bit: usize) {
+        self.toggle_range(bit..bit + 1);
+    }
+
+    pub fn set_range(&mut self, range: Range<usize>) {
+        self.grow(range.end);
+        for_each_word(&mut self.words, range, |word, mask| *word |= mask);
+    }
+
+    /// Bits past the end are already clear, so this never grows the set.
+    pub fn clear_range(&mut self, range: Range<usize>) {
+        let end = range.end.min(self.words.len() * BITS);
+        for_each_word(&mut self.words, range.start..end, |word, mask| *word &= !mask);
+    }
+
+    pub fn toggle_range(&mut self, range: Range<usize>) {
+        self.grow(range.end);
+        for_each_word(&mut self.words, range, |word, mask| *word ^= mask);
+    }
+
+    /// The number of set bits.
+    pub fn count_ones(&self) -> usize {
+        count_ones(&self.words)
+    }
+
+    /// The set bits, in increasing order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        ones(&self.words)
+    }
+
+    // Missing words of either set are taken as clear.
+    fn combine(&mut self, other: &BitSet, op: impl Fn(u64, u64) -> u64) {
+        self.grow(other.words.len() * BITS);
+        for (i, word) in self.words.iter_mut().enumerate() {
+            *word = op(*word, other.words.get(i).copied().unwrap_or(0));
+        }
+    }
+
+    pub fn union_with(&mut self, other: &BitSet) {
+        self.combine(other, |a, b| a | b);
+    }
+
+    pub fn intersect_with(&mut self, other: &BitSet) {
+        self.combine(other, |a, b| a & b);
+    }
+
+    pub fn difference_with(&mut self, other: &BitSet) {
+        self.combine(other, |a, b| a & !b);
+    }
+
+    pub fn symmetric_difference_with(&mut self, other: &BitSet) {
+        self.combine(other, |a, b| a ^ b);
+    }
+}
+
+/// Sets are equal when they have the same bits set, however far they grew.
+impl PartialEq for BitSet {
+    fn eq(&self, other: &BitSet) -> bool {
+        let (short, long) = if self.words.len() <= other.words.len() {
+            (&self.words, &other.words)
+        } else {
+            (&other.words, &self.words)
+        };
+
+        long[..short.len()] == short[..] && long[short.len()..].iter().all(|&w| w == 0)
+    }
+}
+
+impl Eq for BitSet {}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(bits: I) -> Self {
+        let mut set = BitSet::new();
+        set.extend(bits);
+        set
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, bits: I) {
+        for bit in bits {
+            self.set(bit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ranges_across_words() {
+        let mut bits = FixedBitSet::new(200);
+        bits.set_range(60..130);
+        assert_eq!(bits.count_ones(), 70);
+        assert_eq!(bits.words(), [0xf << 60, u64::MAX, 0b11, 0]);
+
+        bits.toggle_range(0..64);
+        bits.clear(129);
+        assert_eq!(bits.count_ones(), 60 + 64 + 1);
+        assert!(!bits.get(63) && bits.get(64) && bits.get(128) && !bits.get(129));
+
+        bits.toggle_range(5..5);
+        bits.set_range(199..200);
+        assert_eq!(bits.ones().last(), Some(199));
+    }
+
+    #[test]
+    #[should_panic(expected = "outside a set of 10 bits")]
+    fn fixed_sets_do_not_grow() {
+        FixedBitSet::new(10).set_range(5..11);
+    }
+
+    #[test]
+    fn word_operations() {
+        let mut a = FixedBitSet::new(70);
+        let mut b = FixedBitSet::new(70);
+        a.set_range(0..40);
+        b.set_range(30..70);
+
+        let mut union = a.clone();
+        union.union_with(&b);
+        let mut both = a.clone();
+        both.intersect_with(&b);
+        let mut only_a = a.clone();
+        only_a.difference_with(&b);
+        a.symmetric_difference_with(&b);
+
+        assert_eq!((union.count_ones(), both.count_ones(), only_a.count_ones(), a.count_ones()), (70, 10, 30, 60));
+    }
+
+    #[test]
+    fn growable_sets() {
+        let mut set = [3, 500, 64].into_iter().collect::<BitSet>();
+        assert_eq!(set.ones().collect::<Vec<_>>(), [3, 64, 500]);
+        assert!(!set.get(10_000));
+
+        set.clear_range(0..1_000_000);
+        assert_eq!(set, BitSet::new());
+
+        set.toggle_range(100..103);
+        let mut other = BitSet::from_iter([101]);
+        other.symmetric_difference_with(&set);
+        assert_eq!(other.ones().collect::<Vec<_>>(), [100, 102]);
+
+        other.intersect_with(&BitSet::from_iter([102]));
+        assert_eq!(other, BitSet::from_iter([102]));
+    }
+}
@@ -0,0 +1,283 @@
+//! Lazy iterators over arrangements and selections of items.
+//!
+//! Every iterator keeps a single working buffer and builds the next result
+//! from the previous one, so going through all of them never holds more than
+//! one in memory. The `Iterator` impls hand out owned copies; the `next_*`
+//! methods borrow the buffer instead when even that is too much.
+
+/// Every ordering of `items`, generated with Heap's algorithm so that each
+/// one differs from the previous by a single swap. There is exactly one
+/// ordering of no items.
+pub fn permutations<T>(items: Vec<T>) -> Permutations<T> {
+    let count = items.len();
+
+    Permutations {
+        items,
+        counters: vec![0; count],
+        position: 0,
+        started: false,
+    }
+}
+
+pub struct Permutations<T> {
+    items: Vec<T>,
+    counters: Vec<usize>,
+    position: usize,
+    started: bool,
+}
+
+impl<T> Permutations<T> {
+    /// Moves to the next ordering and borrows it.
+    pub fn next_permutation(&mut self) -> Option<&[T]> {
+        if !self.started {
+            self.started = true;
+            return Some(&self.items);
+        }
+
+        while self.position < self.items.len() {
+            let i = self.position;
+            if self.counters[i] < i {
+                let other = if i.is_multiple_of(2) { 0 } else { self.counters[i] };
+                self.items.swap(other, i);
+                self.counters[i] += 1;
+                self.position = 0;
+
+                return Some(&self.items);
+            }
+
+            self.counters[i] = 0;
+            self.position += 1;
+        }
+
+        None
+    }
+}
+
+impl<T: Clone> Iterator for Permutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        self.next_permutation().map(<[T]>::to_vec)
+    }
+}
+
+/// Every way of choosing `k` of `items`, in the order they appear in
+/// `items`.
+pub fn combinations<T: Clone>(items: &[T], k: usize) -> Combinations<T> {
+    Combinations {
+        items: items.to_vec(),
+        indices: (0..k).collect(),
+        started: false,
+    }
+}
+
+pub struct Combinations<T> {
+    items: Vec<T>,
+    indices: Vec<usize>,
+    started: bool,
+}
+
+impl<T> Combinations<T> {
+    /// Moves to the next choice and borrows the indices of the chosen items,
+    /// in increasing order.
+    pub fn next_indices(&mut self) -> Option<&[usize]> {
+        let n = self.items.len();
+        let k = self.indices.len();
+
+        if k > n {
+            return None;
+        }
+
+        if !self.started {
+            self.started = true;
+            return Some(&self.indices);
+        }
+
+        // the rightmost index that can still move right
+        let i = (0..k).rev().find(|&i| self.indices[i] < n - k + i)?;
+        self.indices[i] += 1;
+        for j in i + 1..k {
+            self.indices[j] = self.indices[j - 1] + 1;
+        }
+
+        Some(&self.indices)
+    }
+}
+
+impl<T: Clone> Iterator for Combinations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        self.next_indices()?;
+        Some(self.indices.iter().map(|&i| self.items[i].clone()).collect())
+    }
+}
+
+/// Every set of `k` numbers below `n` as a bitmask, from the smallest mask
+/// up. `n` can be at most 64.
+pub fn k_subsets(n: u32, k: u32) -> KSubsets {
+    assert!(n <= 64, "k_subsets supports sets of at most 64 items, got {}", n);
+
+    KSubsets {
+        next: match k {
+            _ if k > n => None,
+            0 => Some(0),
+            _ => Some(u64::MAX >> (64 - k)),
+        },
+        n,
+    }
+}
+
+pub struct KSubsets {
+    next: Option<u64>,
+    n: u32,
+}
+
+impl Iterator for KSubsets {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let current = self.next?;
+
+        // Gosper's hack: the next larger number with as many bits set
+        self.next = if current == 0 {
+            None
+        } else {
+            let lowest = current & current.wrapping_neg();
+            current
+                .checked_add(lowest)
+                .map(|ripple| (((ripple ^ current) >> 2) / lowest) | ripple)
+                .filter(|&next| self.n == 64 || next < 1 << self.n)
+        };
+
+        Some(current)
+    }
+}
+
+/// Every subset of `items`, starting with the empty one. There can be at
+/// most 63 items.
+pub fn power_set<T: Clone>(items: &[T]) -> PowerSet<T> {
+    assert!(items.len() < 64, "power_set supports at most 63 items, got {}", items.len());
+
+    PowerSet {
+        items: items.to_vec(),
+        mask: 0,
+    }
+}
+
+pub struct PowerSet<T> {
+    items: Vec<T>,
+    mask: u64,
+}
+
+impl<T: Clone> Iterator for PowerSet<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.mask >> self.items.len() != 0 {
+            return None;
+        }
+
+        let subset = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.mask & (1 << i) != 0)
+            .map(|(_, item)| item.clone())
+            .collect();
+        self.mask += 1;
+
+        Some(subset)
+    }
+}
+
+/// Every way of picking one item from each of `lists`, with the last list
+/// changing fastest.
+pub fn cartesian_product<T: Clone>(lists: &[Vec<T>]) -> CartesianProduct<T> {
+    CartesianProduct {
+        lists: lists.to_vec(),
+        indices: vec![0; lists.len()],
+        done: lists.iter().any(Vec::is_empty),
+    }
+}
+
+pub struct CartesianProduct<T> {
+    lists: Vec<Vec<T>>,
+    indices: Vec<usize>,
+    done: bool,
+}
+
+impl<T: Clone> Iterator for CartesianProduct<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.done {
+            return None;
+        }
+
+        let current = self
+            .indices
+            .iter()
+            .zip(&self.lists)
+            .map(|(&i, list)| list[i].clone())
+            .collect();
+
+        // count up like an odometer
+        self.done = true;
+        for (index, list) in self.indices.iter_mut().zip(&self.lists).rev() {
+            *index += 1;
+            if *index < list.len() {
+                self.done = false;
+                break;
+            }
+            *index = 0;
+        }
+
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn permutations_are_all_different() {
+        for n in 0..7 {
+            let perms = permutations((0..n).collect::<Vec<_>>()).collect::<HashSet<_>>();
+            let factorial = (1..=n).product::<usize>();
+
+            assert_eq!(perms.len(), factorial, "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn combinations_in_order() {
+        let got = combinations(&['a', 'b', 'c', 'd'], 2)
+            .map(|c| c.into_iter().collect::<String>())
+            .collect::<Vec<_>>();
+
+        assert_eq!(got, vec!["ab", "ac", "ad", "bc", "bd", "cd"]);
+        assert_eq!(combinations(&[1, 2], 3).count(), 0);
+        assert_eq!(combinations(&[1, 2], 0).collect::<Vec<_>>(), vec![Vec::<i32>::new()]);
+    }
+
+    #[test]
+    fn k_subsets_as_masks() {
+        assert_eq!(k_subsets(4, 2).collect::<Vec<_>>(), vec![0b0011, 0b0101, 0b0110, 0b1001, 0b1010, 0b1100]);
+        assert_eq!(k_subsets(64, 63).count(), 64);
+        assert_eq!(k_subsets(3, 0).collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn power_set_and_product() {
+        assert_eq!(
+            power_set(&[1, 2]).collect::<Vec<_>>(),
+            vec![vec![], vec![1], vec![2], vec![1, 2]]);
+
+        assert_eq!(
+            cartesian_product(&[vec![1, 2], vec![3], vec![4, 5]]).collect::<Vec<_>>(),
+            vec![vec![1, 3, 4], vec![1, 3, 5], vec![2, 3, 4], vec![2, 3, 5]]);
+    }
+}
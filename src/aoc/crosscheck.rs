@@ -0,0 +1,254 @@
+use crate::aoc::generator::InputGenerator;
+use crate::aoc::panic::catch;
+use crate::aoc::rng::Rng;
+use crate::aoc::{Error, Result};
+use std::fmt;
+
+/// A named implementation of one part of a puzzle.
+pub type Implementation<T> = (&'static str, fn(&str) -> Result<T>);
+
+/// Days that have more than one implementation of the same part register
+/// them here so they can be checked against each other. The first
+/// implementation of each part is taken as the reference.
+pub trait Alternatives<T> {
+    fn part1_implementations(&self) -> Vec<Implementation<T>>;
+
+    fn part2_implementations(&self) -> Vec<Implementation<T>>;
+}
+
+pub struct Config {
+    pub cases: usize,
+    pub seed: u64,
+    pub max_size: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            cases: 100,
+            seed: 0,
+            max_size: 8,
+        }
+    }
+}
+
+/// Different results for the same input, after shrinking it.
+pub struct Disagreement {
+    pub seed: u64,
+    pub size: usize,
+    pub input: String,
+    pub outcomes: Vec<(&'static str, String)>,
+}
+
+impl fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "implementations disagree (seed {}, size {})", self.seed, self.size)?;
+        writeln!(f, "minimal input:")?;
+        for line in self.input.lines() {
+            writeln!(f, "    {}", line)?;
+        }
+        for (name, outcome) in self.outcomes.iter() {
+            writeln!(f, "{}: {}", name, outcome)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+enum Outcome<T> {
+    Value(T),
+    Error(String),
+    Panic(String),
+}
+
+impl<T: fmt::Display> fmt::Display for Outcome<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Value(v) => write!(f, "{}", v),
+            Outcome::Error(e) => write!(f, "error: {}", e),
+            Outcome::Panic(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Runs every implementation on `config.cases` generated inputs and
+/// returns the number of cases checked, or the first disagreement shrunk
+/// to a minimal input.
+///
+/// Implementations agree when they all return the same value or when they
+/// all return an error. A panic is always a disagreement.
+pub fn cross_check<T: PartialEq + fmt::Display>(
+    generator: &dyn InputGenerator,
+    implementations: &[Implementation<T>],
+    config: &Config,
+) -> std::result::Result<usize, Disagreement> {
+    let mut rng = Rng::new(config.seed);
+
+    for _ in 0..config.cases {
+        let seed = rng.next_u64();
+        let size = rng.range(1, config.max_size.max(1) as i64) as usize;
+        let input = generator.generate(&mut Rng::new(seed), size);
+
+        let outcomes = run_all(implementations, &input);
+        if agree(&outcomes) {
+            continue;
+        }
+
+        // when every implementation produced an answer, keep shrinking
+        // towards inputs that are still answered, otherwise the smallest
+        // input is usually one that one implementation merely rejects
+        let all_values = outcomes.iter().all(|o| matches!(o, Outcome::Value(_)));
+        let input = shrink(&input, |candidate| {
+            let outcomes = run_all(implementations, candidate);
+            !agree(&outcomes)
+                && (!all_values || outcomes.iter().all(|o| matches!(o, Outcome::Value(_))))
+        });
+
+        let outcomes = run_all(implementations, &input)
+            .into_iter()
+            .zip(implementations)
+            .map(|(outcome, (name, _))| (*name, outcome.to_string()))
+            .collect();
+
+        return Err(Disagreement {
+            seed,
+            size,
+            input,
+            outcomes,
+        });
+    }
+
+    Ok(config.cases)
+}
+
+fn run_all<T>(implementations: &[Implementation<T>], input: &str) -> Vec<Outcome<T>> {
+    implementations
+        .iter()
+        .map(|(_, solve)| match catch(|| solve(input)) {
+            Ok(value) => Outcome::Value(value),
+            Err(e @ Error::Panic{..}) => Outcome::Panic(e.to_string()),
+            Err(e) => Outcome::Error(e.to_string()),
+        })
+        .collect()
+}
+
+fn agree<T: PartialEq>(outcomes: &[Outcome<T>]) -> bool {
+    let all_errors = outcomes.iter().all(|o| matches!(o, Outcome::Error(_)));
+    let all_equal = match outcomes.first() {
+        Some(Outcome::Value(first)) => outcomes
+            .iter()
+            .all(|o| matches!(o, Outcome::Value(v) if v == first)),
+        _ => false,
+    };
+
+    all_errors || all_equal
+}
+
+/// Removes chunks of lines as long as the input stays interesting. Inputs
+/// that end up as a single line are then shrunk character by character.
+fn shrink(input: &str, interesting: impl Fn(&str) -> bool) -> String {
+    let lines = input.trim().lines().map(|l| l.trim()).collect::<Vec<_>>();
+    let lines = shrink_units(lines, |ls| interesting(&ls.join("\n")));
+    let input = lines.join("\n");
+
+    if lines.len() > 1 {
+        return input;
+    }
+
+    let chars = input.chars().collect::<Vec<_>>();
+    let chars = shrink_units(chars, |cs| interesting(&cs.iter().collect::<String>()));
+
+    chars.into_iter().collect()
+}
+
+fn shrink_units<U: Clone>(mut units: Vec<U>, interesting: impl Fn(&[U]) -> bool) -> Vec<U> {
+    let mut chunk = units.len() / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        while start < units.len() {
+            let end = (start + chunk).min(units.len());
+            let candidate = [&units[..start], &units[end..]].concat();
+
+            if interesting(&candidate) {
+                units = candidate;
+            } else {
+                start += chunk;
+            }
+        }
+
+        chunk /= 2;
+    }
+
+    units
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Parens;
+
+    impl InputGenerator for Parens {
+        fn generate(&self, rng: &mut Rng, size: usize) -> String {
+            (0..size * 10)
+                .map(|_| if rng.chance(1, 2) { '(' } else { ')' })
+                .collect()
+        }
+    }
+
+    fn floor(input: &str) -> Result<i32> {
+        Ok(input.chars().map(|c| if c == '(' { 1 } else { -1 }).sum())
+    }
+
+    fn floor_by_count(input: &str) -> Result<i32> {
+        let up = input.matches('(').count() as i32;
+        Ok(2 * up - input.len() as i32)
+    }
+
+    // wrong whenever two closing parens are next to each other
+    fn floor_buggy(input: &str) -> Result<i32> {
+        Ok(floor(input)? + input.matches("))").count() as i32)
+    }
+
+    fn floor_rejecting(_: &str) -> Result<i32> {
+        Err(Error::InvalidInput)
+    }
+
+    #[test]
+    fn agreeing_implementations() {
+        let result = cross_check(
+            &Parens,
+            &[("sum", floor), ("count", floor_by_count)],
+            &Config::default());
+
+        assert_eq!(result.unwrap(), 100);
+    }
+
+    #[test]
+    fn disagreement_is_shrunk() {
+        let result = cross_check(
+            &Parens,
+            &[("sum", floor), ("buggy", floor_buggy)],
+            &Config::default());
+
+        let disagreement = result.unwrap_err();
+        assert_eq!(disagreement.input, "))");
+        assert_eq!(disagreement.outcomes, vec![("sum", "-2".to_owned()), ("buggy", "-1".to_owned())]);
+    }
+
+    #[test]
+    fn error_against_value_is_a_disagreement() {
+        let result = cross_check(
+            &Parens,
+            &[("sum", floor), ("rejecting", floor_rejecting)],
+            &Config::default());
+
+        assert_eq!(result.unwrap_err().input, "");
+    }
+}
@@ -0,0 +1,440 @@
+//! Stepping forward and backward through simulation-style solutions, with
+//! breakpoints on conditions over their state.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use crate::aoc::trace::Fields;
+use crate::aoc::{Error, Result};
+
+/// A solution running one step at a time.
+pub trait Simulation<'a> {
+    /// Runs the next step, or returns `false` if there is nothing left to
+    /// do, leaving the state as it was.
+    fn step(&mut self) -> Result<bool>;
+
+    /// Named values describing the current state, which breakpoints are
+    /// checked against.
+    fn fields(&self) -> Fields;
+
+    /// A picture of the current state, if it is small enough to print.
+    fn render(&self) -> Option<String> {
+        None
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Simulation<'a> + 'a>;
+}
+
+pub trait Simulate {
+    /// The simulation of `input`, before its first step.
+    fn simulate<'a>(&self, input: &'a str) -> Result<Box<dyn Simulation<'a> + 'a>>;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+/// A condition like `floor == -1` on one of the fields of a simulation.
+/// Values are compared as numbers when both sides are numbers, and as text
+/// otherwise.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Condition {
+    field: String,
+    comparison: Comparison,
+    value: String,
+}
+
+impl Condition {
+    /// Missing fields never match.
+    pub fn matches(&self, fields: &Fields) -> bool {
+        let actual = match fields.iter().find(|(name, _)| *name == self.field) {
+            Some((_, actual)) => actual,
+            None => return false,
+        };
+
+        let ordering = match (actual.parse::<i64>(), self.value.parse::<i64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => actual.as_str().cmp(self.value.as_str()),
+        };
+
+        match self.comparison {
+            Comparison::Equal => ordering == Ordering::Equal,
+            Comparison::NotEqual => ordering != Ordering::Equal,
+            Comparison::Less => ordering == Ordering::Less,
+            Comparison::LessOrEqual => ordering != Ordering::Greater,
+            Comparison::Greater => ordering == Ordering::Greater,
+            Comparison::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let tokens = text.split_whitespace().collect::<Vec<_>>();
+        let (field, comparison, value) = match tokens[..] {
+            [field, comparison, value] => (field, comparison, value),
+            _ => return Err(Error::parse(text, text.trim(), "a condition like `floor == -1`")),
+        };
+
+        let comparison = match comparison {
+            "==" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            _ => return Err(Error::parse(text, comparison, "one of `==`, `!=`, `<`, `<=`, `>` or `>=`")),
+        };
+
+        Ok(Condition { field: field.to_owned(), comparison, value: value.to_owned() })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let comparison = match self.comparison {
+            Comparison::Equal => "==",
+            Comparison::NotEqual => "!=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+        };
+
+        write!(f, "{} {} {}", self.field, comparison, self.value)
+    }
+}
+
+// Going back restores the last checkpoint before the target step and runs
+// forward from there, so only one state in this many is kept.
+const CHECKPOINT_EVERY: usize = 64;
+
+/// Drives a simulation, remembering enough of its past to go back to any
+/// earlier step.
+pub struct Debugger<'a> {
+    simulation: Box<dyn Simulation<'a> + 'a>,
+    step: usize,
+    finished: bool,
+    checkpoints: Vec<Box<dyn Simulation<'a> + 'a>>,
+    breakpoints: Vec<Condition>,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(simulation: Box<dyn Simulation<'a> + 'a>) -> Self {
+        let checkpoints = vec![simulation.boxed_clone()];
+        Debugger { simulation, step: 0, finished: false, checkpoints, breakpoints: vec![] }
+    }
+
+    /// How many steps were run to get to the current state.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn fields(&self) -> Fields {
+        self.simulation.fields()
+    }
+
+    pub fn render(&self) -> Option<String> {
+        self.simulation.render()
+    }
+
+    pub fn breakpoints(&self) -> &[Condition] {
+        &self.breakpoints
+    }
+
+    pub fn add_breakpoint(&mut self, condition: Condition) {
+        self.breakpoints.push(condition);
+    }
+
+    pub fn remove_breakpoint(&mut self, index: usize) -> Option<Condition> {
+        (index < self.breakpoints.len()).then(|| self.breakpoints.remove(index))
+    }
+
+    /// Runs one step, returning `false` if the simulation was already over.
+    pub fn forward(&mut self) -> Result<bool> {
+        if self.finished || !self.simulation.step()? {
+            self.finished = true;
+            return Ok(false);
+        }
+
+        self.step += 1;
+        if self.step.is_multiple_of(CHECKPOINT_EVERY) && self.checkpoints.len() == self.step / CHECKPOINT_EVERY {
+            self.checkpoints.push(self.simulation.boxed_clone());
+        }
+
+        Ok(true)
+    }
+
+    /// Goes back one step, returning `false` if already at the start.
+    pub fn back(&mut self) -> Result<bool> {
+        if self.step == 0 {
+            return Ok(false);
+        }
+
+        self.goto(self.step - 1)?;
+        Ok(true)
+    }
+
+    /// Goes to the state after `step` steps, or to the end if the
+    /// simulation finishes before.
+    pub fn goto(&mut self, step: usize) -> Result<()> {
+        if step < self.step {
+            let checkpoint = (step / CHECKPOINT_EVERY).min(self.checkpoints.len() - 1);
+            self.simulation = self.checkpoints[checkpoint].boxed_clone();
+            self.step = checkpoint * CHECKPOINT_EVERY;
+            self.finished = false;
+        }
+
+        while self.step < step && self.forward()? {}
+
+        Ok(())
+    }
+
+    /// Runs until a breakpoint matches after a step, returning its index,
+    /// or until the simulation is over.
+    pub fn run(&mut self) -> Result<Option<usize>> {
+        while self.forward()? {
+            let fields = self.simulation.fields();
+            if let Some(i) = self.breakpoints.iter().position(|b| b.matches(&fields)) {
+                return Ok(Some(i));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+const HELP: &str = "\
+commands:
+  s, step [n]       run n steps, 1 by default
+  b, back [n]       go back n steps, 1 by default
+  g, goto <n>       go to the state after n steps
+  c, continue       run until a breakpoint matches or the end
+  break <cond>      stop when a condition like `floor == -1` holds
+  delete <n>        remove breakpoint n
+  breakpoints       list the breakpoints
+  p, print          show the state
+  r, render         draw the state
+  q, quit           leave the debugger";
+
+/// Reads commands from `commands` and writes what they do to `out`, until
+/// `quit` or the end of the commands.
+pub fn repl(debugger: &mut Debugger, commands: &mut dyn BufRead, out: &mut dyn Write) -> Result<()> {
+    let io_error = |e: io::Error| Error::GenericError {
+        message: format!("debugger input or output failed: {}", e),
+        source: Box::new(e),
+    };
+
+    writeln!(out, "{}", HELP).map_err(io_error)?;
+    show_state(debugger, out).map_err(io_error)?;
+
+    loop {
+        write!(out, "(step {}) ", debugger.step()).map_err(io_error)?;
+        out.flush().map_err(io_error)?;
+
+        let mut line = String::new();
+        match commands.read_line(&mut line) {
+            Ok(0) => return Ok(()),
+            Ok(_) => (),
+            Err(e) => return Err(io_error(e)),
+        }
+
+        let (command, argument) = match line.trim().split_once(' ') {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line.trim(), ""),
+        };
+        if matches!(command, "q" | "quit") {
+            return Ok(());
+        }
+
+        // errors from a command are shown and the session goes on
+        let message = match run_command(debugger, command, argument) {
+            Ok(Some(message)) => message,
+            Ok(None) => {
+                show_state(debugger, out).map_err(io_error)?;
+                continue;
+            }
+            Err(e) => format!("error: {}", e),
+        };
+        writeln!(out, "{}", message).map_err(io_error)?;
+    }
+}
+
+// The text to show, or `None` to show the state.
+fn run_command(debugger: &mut Debugger, command: &str, argument: &str) -> Result<Option<String>> {
+    let count = |default: usize| match argument {
+        "" => Ok(default),
+        n => n.parse::<usize>().map_err(|_| Error::parse(argument, n, "a number of steps")),
+    };
+
+    match command {
+        "" | "s" | "step" => {
+            for _ in 0..count(1)? {
+                if !debugger.forward()? {
+                    break;
+                }
+            }
+        }
+        "b" | "back" => {
+            let target = debugger.step().saturating_sub(count(1)?);
+            debugger.goto(target)?;
+        }
+        "g" | "goto" => debugger.goto(count(debugger.step())?)?,
+        "c" | "continue" => {
+            if let Some(i) = debugger.run()? {
+                return Ok(Some(format!("breakpoint {} `{}`\n{}", i, debugger.breakpoints()[i], fields_text(debugger))));
+            }
+        }
+        "break" => {
+            debugger.add_breakpoint(argument.parse()?);
+            return Ok(Some(format!("breakpoint {} set", debugger.breakpoints().len() - 1)));
+        }
+        "delete" => {
+            let removed = argument.parse().ok().and_then(|i| debugger.remove_breakpoint(i));
+            return Ok(Some(match removed {
+                Some(condition) => format!("removed `{}`", condition),
+                None => format!("no breakpoint '{}'", argument),
+            }));
+        }
+        "breakpoints" => {
+            let list = debugger
+                .breakpoints()
+                .iter()
+                .enumerate()
+                .map(|(i, b)| format!("{}: {}", i, b))
+                .collect::<Vec<_>>();
+            return Ok(Some(if list.is_empty() { "no breakpoints".to_owned() } else { list.join("\n") }));
+        }
+        "p" | "print" => (),
+        "r" | "render" => {
+            return Ok(Some(debugger.render().unwrap_or_else(|| "nothing to draw".to_owned())));
+        }
+        "h" | "help" => return Ok(Some(HELP.to_owned())),
+        _ => return Ok(Some(format!("unknown command '{}', try `help`", command))),
+    }
+
+    Ok(None)
+}
+
+fn show_state(debugger: &Debugger, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "{}", fields_text(debugger))
+}
+
+fn fields_text(debugger: &Debugger) -> String {
+    let fields = debugger
+        .fields()
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if debugger.is_finished() {
+        format!("{} (finished)", fields)
+    } else {
+        fields
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Clone)]
+    struct Counter {
+        value: i64,
+        limit: i64,
+    }
+
+    impl<'a> Simulation<'a> for Counter {
+        fn step(&mut self) -> Result<bool> {
+            if self.value == self.limit {
+                return Ok(false);
+            }
+
+            self.value += 1;
+            Ok(true)
+        }
+
+        fn fields(&self) -> Fields {
+            vec![("value", self.value.to_string())]
+        }
+
+        fn boxed_clone(&self) -> Box<dyn Simulation<'a> + 'a> {
+            Box::new(self.clone())
+        }
+    }
+
+    fn counter(limit: i64) -> Debugger<'static> {
+        Debugger::new(Box::new(Counter { value: 0, limit }))
+    }
+
+    #[test]
+    fn conditions() {
+        let fields = vec![("floor", "-1".to_owned()), ("wire", "x".to_owned())];
+
+        assert!("floor == -1".parse::<Condition>().unwrap().matches(&fields));
+        assert!("floor < 3".parse::<Condition>().unwrap().matches(&fields));
+        assert!("wire != y".parse::<Condition>().unwrap().matches(&fields));
+        assert!(!"missing == 1".parse::<Condition>().unwrap().matches(&fields));
+
+        match "floor ~ 1".parse::<Condition>() {
+            Err(Error::Parse(e)) => assert_eq!(e.column, 7),
+            other => panic!("expected a parse error, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn forward_and_back() {
+        let mut debugger = counter(200);
+        debugger.goto(150).unwrap();
+        assert_eq!(debugger.fields(), vec![("value", "150".to_owned())]);
+
+        for _ in 0..100 {
+            assert!(debugger.back().unwrap());
+        }
+        assert_eq!((debugger.step(), debugger.fields()), (50, vec![("value", "50".to_owned())]));
+
+        debugger.goto(1_000).unwrap();
+        assert!(debugger.is_finished());
+        assert_eq!(debugger.step(), 200);
+    }
+
+    #[test]
+    fn breakpoints_stop_the_run() {
+        let mut debugger = counter(100);
+        debugger.add_breakpoint("value >= 42".parse().unwrap());
+
+        assert_eq!(debugger.run().unwrap(), Some(0));
+        assert_eq!(debugger.step(), 42);
+
+        debugger.remove_breakpoint(0);
+        assert_eq!(debugger.run().unwrap(), None);
+        assert_eq!(debugger.step(), 100);
+    }
+
+    #[test]
+    fn commands() {
+        let mut debugger = counter(10);
+        let mut out = vec![];
+        repl(&mut debugger, &mut "step 3\nbreak value == 7\nc\nback 2\nq\nstep\n".as_bytes(), &mut out).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("(step 3) breakpoint 0 set"));
+        assert!(text.contains("breakpoint 0 `value == 7`\nvalue=7"));
+        assert_eq!(debugger.step(), 5);
+    }
+}
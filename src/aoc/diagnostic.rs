@@ -0,0 +1,129 @@
+use std::fmt;
+
+/// Where and why an input could not be parsed.
+///
+/// Lines and columns start at 1, and columns count characters rather than
+/// bytes so the caret lines up with what is shown on screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub len: usize,
+    pub text: String,
+    pub expected: String,
+}
+
+impl ParseError {
+    /// Builds the error for `span`, which must be a slice of `input`. An
+    /// empty span points right after the preceding text, e.g. to report
+    /// something missing at the end of a line.
+    pub fn at(input: &str, span: &str, expected: impl Into<String>) -> Self {
+        let start = offset_in(input, span);
+        let end = start + span.len();
+
+        let line_start = input[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = input[start..].find('\n').map_or(input.len(), |i| start + i);
+        let text = input[line_start..line_end].trim_end_matches('\r');
+
+        ParseError {
+            line: input[..start].matches('\n').count() + 1,
+            column: input[line_start..start].chars().count() + 1,
+            len: input[start..end.min(line_end)].chars().count().max(1),
+            text: text.to_owned(),
+            expected: expected.into(),
+        }
+    }
+
+    /// Builds the error from a line and column reported by another parser,
+    /// such as `serde_json`.
+    pub fn at_position(input: &str, line: usize, column: usize, expected: impl Into<String>) -> Self {
+        let text = input.lines().nth(line.saturating_sub(1)).unwrap_or("");
+
+        ParseError {
+            line: line.max(1),
+            column: column.max(1),
+            len: 1,
+            text: text.to_owned(),
+            expected: expected.into(),
+        }
+    }
+}
+
+// Byte offset of `span` inside `input`. Spans that are not slices of the
+// input point to its end.
+fn offset_in(input: &str, span: &str) -> usize {
+    let start = input.as_ptr() as usize;
+    let position = span.as_ptr() as usize;
+
+    if position < start || position + span.len() > start + input.len() {
+        return input.len();
+    }
+
+    position - start
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: expected {}", self.line, self.column, self.expected)?;
+
+        // errors found while streaming a long line may not have its text
+        if self.text.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f)?;
+        writeln!(f, "    {}", self.text)?;
+        write!(f, "    {}{}", " ".repeat(self.column.saturating_sub(1)), "^".repeat(self.len))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn position_of_span() {
+        let input = "turn on 0,0 through 9,9\ntoggle 1,x through 2,2\n";
+        let span = &input[31..34];
+
+        let error = ParseError::at(input, span, "a coordinate pair");
+
+        assert_eq!(error.line, 2);
+        assert_eq!(error.column, 8);
+        assert_eq!(error.len, 3);
+        assert_eq!(error.text, "toggle 1,x through 2,2");
+    }
+
+    #[test]
+    fn empty_span_at_end_of_line() {
+        let input = "123 ->\n";
+        let error = ParseError::at(input, &input[6..6], "a wire name");
+
+        assert_eq!((error.line, error.column, error.len), (1, 7, 1));
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        let input = "\"é\\q\"";
+        let error = ParseError::at(input, &input[3..5], "an escape sequence");
+
+        assert_eq!((error.column, error.len), (3, 2));
+    }
+
+    #[test]
+    fn display_has_caret_under_span() {
+        let input = "2x3x4\n2xyx4";
+        let error = ParseError::at(input, &input[8..9], "a number");
+
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 3: expected a number\n    2xyx4\n      ^");
+    }
+
+    #[test]
+    fn display_with_column_zero() {
+        let error = ParseError { line: 1, column: 0, len: 1, text: "x".to_owned(), expected: "a digit".to_owned() };
+
+        assert_eq!(error.to_string(), "line 1, column 0: expected a digit\n    x\n    ^");
+    }
+}
@@ -0,0 +1,52 @@
+use crate::aoc::rng::Rng;
+use std::collections::HashSet;
+
+/// Produces random, well formed puzzle inputs for a day.
+///
+/// The meaning of `size` depends on the puzzle (characters, lines, cities,
+/// ...) and is documented on each implementation. The same seed and size
+/// always produce the same input.
+pub trait InputGenerator {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String;
+}
+
+/// Returns `count` distinct capitalized names, e.g. `Rixo`, `Telamu`.
+pub fn unique_names(rng: &mut Rng, count: usize) -> Vec<String> {
+    const CONSONANTS: &[char] = &['b', 'd', 'f', 'g', 'k', 'l', 'm', 'n', 'p', 'r', 's', 't', 'v', 'x', 'z'];
+    const VOWELS: &[char] = &['a', 'e', 'i', 'o', 'u'];
+
+    let mut seen = HashSet::new();
+    let mut names = Vec::with_capacity(count);
+    while names.len() < count {
+        let syllables = rng.range(2, 4);
+
+        let mut name = String::new();
+        for i in 0..syllables {
+            let consonant = *rng.choose(CONSONANTS);
+            name.push(if i == 0 { consonant.to_ascii_uppercase() } else { consonant });
+            name.push(*rng.choose(VOWELS));
+        }
+
+        if seen.insert(name.clone()) {
+            names.push(name);
+        }
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unique_names_are_distinct() {
+        let mut rng = Rng::new(0);
+        let mut names = unique_names(&mut rng, 200);
+
+        names.sort();
+        names.dedup();
+
+        assert_eq!(names.len(), 200);
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::hash_map::{self, HashMap};
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use crate::aoc::{Error, Result};
+
+/// A position on an unbounded plane. `y` grows downwards, like the rows of
+/// a puzzle map, so [`Direction::Up`] decreases it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Debug)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Point {
+    pub const ORIGIN: Point = Point { x: 0, y: 0 };
+
+    pub const fn new(x: i64, y: i64) -> Self {
+        Point { x, y }
+    }
+
+    pub fn manhattan(self, other: Point) -> u64 {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    /// The point one step away in `direction`.
+    pub fn step(self, direction: Direction) -> Point {
+        self + direction.delta()
+    }
+
+    /// The four points next to this one in reading order: up, left, right
+    /// and down.
+    pub fn neighbors4(self) -> impl Iterator<Item = Point> {
+        [Point::new(0, -1), Point::new(-1, 0), Point::new(1, 0), Point::new(0, 1)]
+            .into_iter()
+            .map(move |delta| self + delta)
+    }
+
+    /// The eight points around this one in reading order, row by row.
+    pub fn neighbors8(self) -> impl Iterator<Item = Point> {
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| Point::new(dx, dy)))
+            .filter(|&delta| delta != Point::ORIGIN)
+            .map(move |delta| self + delta)
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other: Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, other: Point) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other: Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, other: Point) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<i64> for Point {
+    type Output = Point;
+
+    fn mul(self, factor: i64) -> Point {
+        Point::new(self.x * factor, self.y * factor)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    /// Clockwise, starting from `Up`.
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+    pub fn delta(self) -> Point {
+        match self {
+            Direction::Up => Point::new(0, -1),
+            Direction::Right => Point::new(1, 0),
+            Direction::Down => Point::new(0, 1),
+            Direction::Left => Point::new(-1, 0),
+        }
+    }
+
+    pub fn turn_right(self) -> Direction {
+        Direction::ALL[(self as usize + 1) % 4]
+    }
+
+    pub fn turn_left(self) -> Direction {
+        Direction::ALL[(self as usize + 3) % 4]
+    }
+
+    pub fn reverse(self) -> Direction {
+        Direction::ALL[(self as usize + 2) % 4]
+    }
+
+    /// Parses one of `^`, `>`, `v` or `<`.
+    pub fn from_arrow(c: char) -> Option<Direction> {
+        match c {
+            '^' => Some(Direction::Up),
+            '>' => Some(Direction::Right),
+            'v' => Some(Direction::Down),
+            '<' => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    /// Parses one of `U`, `R`, `D` or `L`.
+    pub fn from_letter(c: char) -> Option<Direction> {
+        match c {
+            'U' => Some(Direction::Up),
+            'R' => Some(Direction::Right),
+            'D' => Some(Direction::Down),
+            'L' => Some(Direction::Left),
+            _ => None,
+        }
+    }
+
+    /// Parses one of `N`, `E`, `S` or `W`, with north being up.
+    pub fn from_compass(c: char) -> Option<Direction> {
+        match c {
+            'N' => Some(Direction::Up),
+            'E' => Some(Direction::Right),
+            'S' => Some(Direction::Down),
+            'W' => Some(Direction::Left),
+            _ => None,
+        }
+    }
+}
+
+/// Accepts any of the arrow, letter or compass notations.
+impl TryFrom<char> for Direction {
+    type Error = Error;
+
+    fn try_from(c: char) -> Result<Direction> {
+        Direction::from_arrow(c)
+            .or_else(|| Direction::from_letter(c))
+            .or_else(|| Direction::from_compass(c))
+            .ok_or(Error::InvalidInput)
+    }
+}
+
+/// Cells on an unbounded plane, only storing the ones that were set.
+#[derive(Clone, Debug)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point, T>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        SparseGrid { cells: HashMap::new() }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, point: Point, value: T) -> Option<T> {
+        self.cells.insert(point, value)
+    }
+
+    pub fn remove(&mut self, point: Point) -> Option<T> {
+        self.cells.remove(&point)
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.cells.get(&point)
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.cells.get_mut(&point)
+    }
+
+    pub fn entry(&mut self, point: Point) -> hash_map::Entry<'_, Point, T> {
+        self.cells.entry(point)
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.cells.contains_key(&point)
+    }
+
+    /// The number of cells that were set.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells.iter().map(|(&p, v)| (p, v))
+    }
+
+    /// The top left and bottom right corners of the smallest rectangle
+    /// holding every cell that was set.
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        let mut points = self.cells.keys();
+        let first = *points.next()?;
+
+        Some(points.fold((first, first), |(min, max), p| {
+            (Point::new(min.x.min(p.x), min.y.min(p.y)), Point::new(max.x.max(p.x), max.y.max(p.y)))
+        }))
+    }
+
+    /// Renders the cells inside [`SparseGrid::bounds`] as characters, one
+    /// row per line. `cell` gets `None` for cells that were never set.
+    pub fn render(&self, cell: impl Fn(Option<&T>) -> char) -> String {
+        let (min, max) = match self.bounds() {
+            Some(bounds) => bounds,
+            None => return String::new(),
+        };
+
+        (min.y..=max.y)
+            .map(|y| (min.x..=max.x).map(|x| cell(self.get(Point::new(x, y)))).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn point_arithmetic() {
+        let p = Point::new(3, -4);
+
+        assert_eq!(p + Point::new(1, 1), Point::new(4, -3));
+        assert_eq!(p - p, Point::ORIGIN);
+        assert_eq!(-p * 2, Point::new(-6, 8));
+        assert_eq!(p.manhattan(Point::ORIGIN), 7);
+        assert_eq!(p.neighbors8().count(), 8);
+    }
+
+    #[test]
+    fn turning_and_parsing() {
+        assert_eq!(Direction::Up.turn_right(), Direction::Right);
+        assert_eq!(Direction::Up.turn_left(), Direction::Left);
+        assert_eq!(Direction::Left.reverse(), Direction::Right);
+
+        let parsed = "^RSW".chars().map(Direction::try_from).collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(parsed, Direction::ALL);
+        assert!(Direction::try_from('x').is_err());
+    }
+
+    #[test]
+    fn sparse_grid_bounds_and_rendering() {
+        let mut grid = SparseGrid::new();
+        let mut position = Point::ORIGIN;
+        grid.insert(position, '#');
+        for d in [Direction::Right, Direction::Down, Direction::Down] {
+            position = position.step(d);
+            grid.insert(position, '#');
+        }
+
+        assert_eq!(grid.bounds(), Some((Point::new(0, 0), Point::new(1, 2))));
+        assert_eq!(grid.render(|c| *c.unwrap_or(&'.')), "##\n.#\n.#");
+    }
+}
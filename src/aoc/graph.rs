@@ -0,0 +1,438 @@
+//! Weighted graphs over dense `usize` nodes, with the usual shortest path
+//! searches, topological sorting and best tours through every node.
+//!
+//! Puzzles name their nodes, so an [`Interner`] turns the names into
+//! indices first. Path lengths that overflow the weight type are errors,
+//! or are left out where a search returns no `Result`.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+
+use crate::aoc::{Error, Result};
+
+/// Gives each distinct name a dense index, in the order they are first
+/// seen, so that graphs can work with plain `usize` nodes.
+#[derive(Clone, Debug)]
+pub struct Interner<K> {
+    index: HashMap<K, usize>,
+    names: Vec<K>,
+}
+
+impl<K> Default for Interner<K> {
+    fn default() -> Self {
+        Interner {
+            index: HashMap::new(),
+            names: vec![],
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone> Interner<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The index of `name`, adding it if it was not seen before.
+    pub fn intern(&mut self, name: K) -> usize {
+        if let Some(&i) = self.index.get(&name) {
+            return i;
+        }
+
+        let i = self.names.len();
+        self.index.insert(name.clone(), i);
+        self.names.push(name);
+        i
+    }
+
+    pub fn get(&self, name: &K) -> Option<usize> {
+        self.index.get(name).copied()
+    }
+
+    pub fn name(&self, index: usize) -> &K {
+        &self.names[index]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// An edge weight. Sums that overflow are never silently wrapped.
+pub trait Weight: Copy + Ord + Default + fmt::Debug {
+    fn checked_add(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! integer_weights {
+    ($($t:ty),*) => {
+        $(
+            impl Weight for $t {
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+            }
+        )*
+    };
+}
+
+integer_weights!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+// The table used for tours has `n << n` entries: about 21 million at 20
+// nodes, which is 168MB for `u32` weights and 335MB for `i64` ones, and
+// every extra node more than doubles it.
+const MAX_TOUR_NODES: usize = 20;
+
+/// A weighted graph over nodes `0..len()`, stored as adjacency lists.
+#[derive(Clone, Debug)]
+pub struct Graph<W> {
+    directed: bool,
+    adjacency: Vec<Vec<(usize, W)>>,
+}
+
+impl<W: Weight> Graph<W> {
+    pub fn directed() -> Self {
+        Graph {
+            directed: true,
+            adjacency: vec![],
+        }
+    }
+
+    /// A graph where every edge can be used both ways.
+    pub fn undirected() -> Self {
+        Graph {
+            directed: false,
+            adjacency: vec![],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.adjacency.is_empty()
+    }
+
+    pub fn add_node(&mut self) -> usize {
+        self.adjacency.push(vec![]);
+        self.adjacency.len() - 1
+    }
+
+    /// Adds an edge, and any node up to `from` and `to` that is missing. An
+    /// edge that already exists gets the new weight.
+    pub fn add_edge(&mut self, from: usize, to: usize, weight: W) {
+        let needed = from.max(to) + 1;
+        if self.adjacency.len() < needed {
+            self.adjacency.resize_with(needed, Vec::new);
+        }
+
+        self.set_edge(from, to, weight);
+        if !self.directed {
+            self.set_edge(to, from, weight);
+        }
+    }
+
+    fn set_edge(&mut self, from: usize, to: usize, weight: W) {
+        match self.adjacency[from].iter_mut().find(|(n, _)| *n == to) {
+            Some(edge) => edge.1 = weight,
+            None => self.adjacency[from].push((to, weight)),
+        }
+    }
+
+    pub fn neighbors(&self, node: usize) -> impl Iterator<Item = (usize, W)> + '_ {
+        self.adjacency[node].iter().copied()
+    }
+
+    pub fn weight(&self, from: usize, to: usize) -> Option<W> {
+        self.neighbors(from).find(|&(n, _)| n == to).map(|(_, w)| w)
+    }
+
+    /// The number of edges from `start` to every node, ignoring weights.
+    pub fn bfs(&self, start: usize) -> Vec<Option<usize>> {
+        let mut steps = vec![None; self.len()];
+        steps[start] = Some(0);
+
+        let mut pending = VecDeque::from([start]);
+        while let Some(node) = pending.pop_front() {
+            let next_steps = steps[node].map(|s| s + 1);
+            for (next, _) in self.neighbors(node) {
+                if steps[next].is_none() {
+                    steps[next] = next_steps;
+                    pending.push_back(next);
+                }
+            }
+        }
+
+        steps
+    }
+
+    /// The length of the shortest path from `start` to every node. Weights
+    /// must not be negative, and paths whose length overflows are ignored.
+    pub fn dijkstra(&self, start: usize) -> Vec<Option<W>> {
+        let mut best = vec![None; self.len()];
+        best[start] = Some(W::default());
+
+        let mut pending = BinaryHeap::from([Reverse((W::default(), start))]);
+        while let Some(Reverse((distance, node))) = pending.pop() {
+            if best[node].is_some_and(|b| b < distance) {
+                continue;
+            }
+
+            for (next, weight) in self.neighbors(node) {
+                let Some(through) = distance.checked_add(weight) else { continue };
+                if best[next].is_none_or(|b| through < b) {
+                    best[next] = Some(through);
+                    pending.push(Reverse((through, next)));
+                }
+            }
+        }
+
+        best
+    }
+
+    /// The shortest path from `start` to `goal` and its length, guided by a
+    /// `heuristic` that must never overestimate the distance left.
+    pub fn astar(&self, start: usize, goal: usize, heuristic: impl Fn(usize) -> W) -> Option<(W, Vec<usize>)> {
+        let mut best = vec![None; self.len()];
+        let mut previous = vec![None; self.len()];
+        best[start] = Some(W::default());
+
+        let mut pending = BinaryHeap::from([Reverse((heuristic(start), start))]);
+        while let Some(Reverse((_, node))) = pending.pop() {
+            let distance = best[node]?;
+            if node == goal {
+                let mut path = vec![goal];
+                while let Some(p) = previous[*path.last()?] {
+                    path.push(p);
+                }
+                path.reverse();
+
+                return Some((distance, path));
+            }
+
+            for (next, weight) in self.neighbors(node) {
+                let Some(through) = distance.checked_add(weight) else { continue };
+                if best[next].is_none_or(|b| through < b) {
+                    best[next] = Some(through);
+                    previous[next] = Some(node);
+                    if let Some(estimate) = through.checked_add(heuristic(next)) {
+                        pending.push(Reverse((estimate, next)));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The length of the shortest path between every pair of nodes.
+    pub fn floyd_warshall(&self) -> Vec<Vec<Option<W>>> {
+        let mut best = self.matrix();
+        for (node, row) in best.iter_mut().enumerate() {
+            row[node] = Some(row[node].map_or(W::default(), |w| w.min(W::default())));
+        }
+
+        for via in 0..self.len() {
+            for from in 0..self.len() {
+                for to in 0..self.len() {
+                    let through = match (best[from][via], best[via][to]) {
+                        (Some(a), Some(b)) => a.checked_add(b),
+                        _ => None,
+                    };
+                    if let Some(through) = through {
+                        if best[from][to].is_none_or(|b| through < b) {
+                            best[from][to] = Some(through);
+                        }
+                    }
+                }
+            }
+        }
+
+        best
+    }
+
+    /// The nodes ordered so that every edge goes forward, or `None` if the
+    /// graph has a cycle.
+    pub fn topological_sort(&self) -> Option<Vec<usize>> {
+        let mut incoming = vec![0; self.len()];
+        for node in 0..self.len() {
+            for (next, _) in self.neighbors(node) {
+                incoming[next] += 1;
+            }
+        }
+
+        let mut ready = (0..self.len()).filter(|&n| incoming[n] == 0).collect::<VecDeque<_>>();
+        let mut order = vec![];
+        while let Some(node) = ready.pop_front() {
+            order.push(node);
+            for (next, _) in self.neighbors(node) {
+                incoming[next] -= 1;
+                if incoming[next] == 0 {
+                    ready.push_back(next);
+                }
+            }
+        }
+
+        (order.len() == self.len()).then_some(order)
+    }
+
+    /// The best length of a path visiting every node once, where `pick`
+    /// chooses the better of two lengths, e.g. `u32::min`.
+    ///
+    /// Fails with `InvalidInput` when the graph is too big or some route
+    /// overflows, and with `ResultNotFound` when there is no such path.
+    pub fn best_hamiltonian_path(&self, pick: impl Fn(W, W) -> W) -> Result<W> {
+        self.best_hamiltonian_path_ends(&pick)?
+            .into_iter()
+            .flatten()
+            .reduce(pick)
+            .ok_or(Error::ResultNotFound)
+    }
+
+    /// Like [`Graph::best_hamiltonian_path`], but one length for each node
+    /// the path can end in, `None` for nodes no such path ends in.
+    pub fn best_hamiltonian_path_ends(&self, pick: impl Fn(W, W) -> W) -> Result<Vec<Option<W>>> {
+        let starts = (0..self.len()).collect::<Vec<_>>();
+
+        self.tours(&starts, &pick)
+    }
+
+    /// Like [`Graph::best_hamiltonian_path`], but the path must also return
+    /// to where it started.
+    pub fn best_hamiltonian_cycle(&self, pick: impl Fn(W, W) -> W) -> Result<W> {
+        if self.is_empty() {
+            return Err(Error::ResultNotFound);
+        }
+
+        // every cycle goes through node 0, so it can start there
+        let ends = self.tours(&[0], &pick)?;
+
+        let mut result = None;
+        for (last, length) in ends.into_iter().enumerate() {
+            let closed = match (length, self.weight(last, 0)) {
+                (Some(length), Some(back)) => length.checked_add(back).ok_or(Error::InvalidInput)?,
+                _ => continue,
+            };
+            result = Some(result.map_or(closed, |r| pick(r, closed)));
+        }
+
+        result.ok_or(Error::ResultNotFound)
+    }
+
+    // Dynamic programming over subsets of visited nodes (Held-Karp), O(2^n *
+    // n^2) instead of the O(n!) of trying every ordering. Returns the best
+    // length of a path from one of `starts` through every node, for each
+    // node it can end in.
+    fn tours(&self, starts: &[usize], pick: &impl Fn(W, W) -> W) -> Result<Vec<Option<W>>> {
+        let count = self.len();
+        if count > MAX_TOUR_NODES {
+            return Err(Error::InvalidInput);
+        }
+
+        // best[visited * count + last] is the best length of a path that
+        // goes through every node in the `visited` bitmask and ends in `last`
+        let mut best: Vec<Option<W>> = vec![None; count << count];
+        for &start in starts {
+            best[(1 << start) * count + start] = Some(W::default());
+        }
+
+        for visited in 1..1 << count {
+            for last in 0..count {
+                let length = match best[visited * count + last] {
+                    Some(l) => l,
+                    None => continue,
+                };
+
+                for (next, weight) in self.neighbors(last).filter(|(n, _)| visited & (1 << n) == 0) {
+                    let route = length.checked_add(weight).ok_or(Error::InvalidInput)?;
+                    let entry = &mut best[(visited | (1 << next)) * count + next];
+                    *entry = Some(entry.map_or(route, |l| pick(l, route)));
+                }
+            }
+        }
+
+        // the last row is the one that visited every node
+        Ok(best.split_off(best.len() - count))
+    }
+
+    fn matrix(&self) -> Vec<Vec<Option<W>>> {
+        let mut matrix = vec![vec![None; self.len()]; self.len()];
+        for (from, row) in matrix.iter_mut().enumerate() {
+            for (to, weight) in self.neighbors(from) {
+                row[to] = Some(weight);
+            }
+        }
+
+        matrix
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 0 -1-> 1 -1-> 2 -1-> 3, plus a 0 -5-> 3 shortcut that is longer
+    fn chain() -> Graph<u32> {
+        let mut graph = Graph::directed();
+        graph.add_edge(0, 1, 1);
+        graph.add_edge(1, 2, 1);
+        graph.add_edge(2, 3, 1);
+        graph.add_edge(0, 3, 5);
+        graph
+    }
+
+    #[test]
+    fn interner_keeps_first_index() {
+        let mut names = Interner::new();
+
+        assert_eq!(names.intern("London"), 0);
+        assert_eq!(names.intern("Dublin"), 1);
+        assert_eq!(names.intern("London"), 0);
+        assert_eq!(*names.name(1), "Dublin");
+        assert_eq!(names.get(&"Belfast"), None);
+    }
+
+    #[test]
+    fn shortest_paths() {
+        let graph = chain();
+
+        assert_eq!(graph.bfs(0), vec![Some(0), Some(1), Some(2), Some(1)]);
+        assert_eq!(graph.dijkstra(0), vec![Some(0), Some(1), Some(2), Some(3)]);
+        assert_eq!(graph.dijkstra(3), vec![None, None, None, Some(0)]);
+        assert_eq!(graph.astar(0, 3, |_| 0), Some((3, vec![0, 1, 2, 3])));
+        assert_eq!(graph.floyd_warshall()[1], vec![None, Some(0), Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn topological_order() {
+        assert_eq!(chain().topological_sort(), Some(vec![0, 1, 2, 3]));
+
+        let mut cycle = chain();
+        cycle.add_edge(3, 0, 1);
+        assert_eq!(cycle.topological_sort(), None);
+    }
+
+    #[test]
+    fn hamiltonian_tours() {
+        // the example from 2015 day 9
+        let mut graph = Graph::undirected();
+        graph.add_edge(0, 1, 464);
+        graph.add_edge(0, 2, 518);
+        graph.add_edge(1, 2, 141);
+
+        assert_eq!(graph.best_hamiltonian_path(u32::min).unwrap(), 605);
+        assert_eq!(graph.best_hamiltonian_path(u32::max).unwrap(), 982);
+        assert_eq!(graph.best_hamiltonian_cycle(u32::min).unwrap(), 1123);
+
+        assert!(matches!(chain().best_hamiltonian_cycle(u32::min), Err(Error::ResultNotFound)));
+
+        let mut big = Graph::undirected();
+        big.add_edge(0, MAX_TOUR_NODES, 1u32);
+        assert!(matches!(big.best_hamiltonian_cycle(u32::min), Err(Error::InvalidInput)));
+    }
+}
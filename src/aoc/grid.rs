@@ -0,0 +1,339 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use crate::aoc::{Error, Result};
+
+/// A dense rectangle of cells stored row by row.
+///
+/// Positions are `(x, y)` pairs, where `x` is the column and `y` the row,
+/// both starting at 0 in the top left corner. Indexing with `grid[(x, y)]`
+/// panics outside the grid, `get` returns `None` instead.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(width: usize, height: usize, mut cell: impl FnMut(usize, usize) -> T) -> Self {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| cell(x, y))
+            .collect();
+
+        Grid { width, height, cells }
+    }
+
+    /// Parses a map with one character per cell, such as
+    ///
+    /// ```text
+    /// #..
+    /// .#.
+    /// ```
+    ///
+    /// Blank lines around the map are ignored, and every row must have the
+    /// same width. Spaces are kept, as some maps use them as cells.
+    pub fn parse(input: &str, mut cell: impl FnMut(char) -> Option<T>, expected: &str) -> Result<Self> {
+        let mut width = None;
+        let mut height = 0;
+        let mut cells = vec![];
+
+        for line in input.trim_matches(['\n', '\r']).lines().map(|l| l.trim_end_matches('\r')) {
+            let row_width = line.chars().count();
+            if *width.get_or_insert(row_width) != row_width {
+                let span = &line[line.len()..];
+                return Err(Error::parse(input, span, format!("a row as wide as the first one ({})", width.unwrap_or(0))));
+            }
+
+            for (i, c) in line.char_indices() {
+                let value = cell(c).ok_or_else(|| Error::parse(input, &line[i..i + c.len_utf8()], expected))?;
+                cells.push(value);
+            }
+            height += 1;
+        }
+
+        Ok(Grid {
+            width: width.unwrap_or(0),
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (x, y): (usize, usize)) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, position: (usize, usize)) -> Option<&T> {
+        self.offset(position).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, position: (usize, usize)) -> Option<&mut T> {
+        self.offset(position).map(|i| &mut self.cells[i])
+    }
+
+    fn offset(&self, (x, y): (usize, usize)) -> Option<usize> {
+        if self.contains((x, y)) {
+            Some(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    /// Every cell with its position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, value)| ((i % width, i / width), value))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "column {} is outside a grid {} wide", x, self.width);
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks of 0 would panic, and an empty grid has no rows anyway
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// The cells in the rectangle between two opposite corners, both
+    /// included, row by row. The corners must be inside the grid.
+    pub fn rect(&self, from: (usize, usize), to: (usize, usize)) -> impl Iterator<Item = &T> {
+        let (xs, ys) = self.rect_ranges(from, to);
+        self.cells
+            .chunks(self.width)
+            .skip(ys.0)
+            .take(ys.1 - ys.0 + 1)
+            .flat_map(move |row| &row[xs.0..=xs.1])
+    }
+
+    /// Like [`Grid::rect`], but the cells can be changed.
+    pub fn rect_mut(&mut self, from: (usize, usize), to: (usize, usize)) -> impl Iterator<Item = &mut T> {
+        let (xs, ys) = self.rect_ranges(from, to);
+        self.cells
+            .chunks_mut(self.width)
+            .skip(ys.0)
+            .take(ys.1 - ys.0 + 1)
+            .flat_map(move |row| &mut row[xs.0..=xs.1])
+    }
+
+    fn rect_ranges(&self, from: (usize, usize), to: (usize, usize)) -> ((usize, usize), (usize, usize)) {
+        assert!(
+            self.contains(from) && self.contains(to),
+            "rectangle {:?} to {:?} is outside a {}x{} grid",
+            from,
+            to,
+            self.width,
+            self.height);
+
+        ((from.0.min(to.0), from.0.max(to.0)), (from.1.min(to.1), from.1.max(to.1)))
+    }
+
+    /// The positions up, left, right and down of `position` that are inside
+    /// the grid, in reading order like [`Point::neighbors4`].
+    ///
+    /// [`Point::neighbors4`]: crate::aoc::geom::Point::neighbors4
+    pub fn neighbors4(&self, position: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbors(position, &[(0, -1), (-1, 0), (1, 0), (0, 1)])
+    }
+
+    /// Like [`Grid::neighbors4`], but also with the diagonals.
+    pub fn neighbors8(&self, position: (usize, usize)) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.neighbors(position, &[(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)])
+    }
+
+    fn neighbors(
+        &self,
+        (x, y): (usize, usize),
+        deltas: &'static [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + '_ {
+        deltas.iter().filter_map(move |&(dx, dy)| {
+            let neighbor = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+            self.contains(neighbor).then_some(neighbor)
+        })
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Renders every cell as a single character, one row per line.
+    pub fn render(&self, cell: impl Fn(&T) -> char) -> String {
+        self.rows()
+            .map(|row| row.iter().map(&cell).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl<T: PartialEq> Grid<T> {
+    /// Groups the positions into regions of equal cells connected up, down,
+    /// left or right, in the order their first cell appears.
+    pub fn regions(&self) -> Vec<Vec<(usize, usize)>> {
+        let mut seen = vec![false; self.cells.len()];
+        let mut regions = vec![];
+
+        for (start, value) in self.iter() {
+            if seen[start.1 * self.width + start.0] {
+                continue;
+            }
+
+            let mut region = vec![];
+            let mut pending = vec![start];
+            seen[start.1 * self.width + start.0] = true;
+            while let Some(position) = pending.pop() {
+                region.push(position);
+                for neighbor in self.neighbors4(position) {
+                    let i = neighbor.1 * self.width + neighbor.0;
+                    if !seen[i] && self.cells[i] == *value {
+                        seen[i] = true;
+                        pending.push(neighbor);
+                    }
+                }
+            }
+
+            region.sort_by_key(|&(x, y)| (y, x));
+            regions.push(region);
+        }
+
+        regions
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: (usize, usize)) -> &T {
+        match self.offset(position) {
+            Some(i) => &self.cells[i],
+            None => panic!("{:?} is outside a {}x{} grid", position, self.width, self.height),
+        }
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, position: (usize, usize)) -> &mut T {
+        match self.offset(position) {
+            Some(i) => &mut self.cells[i],
+            None => panic!("{:?} is outside a {}x{} grid", position, self.width, self.height),
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample() -> Grid<char> {
+        Grid::parse("\n#..\n.##\n..#\n", Some, "a cell").unwrap()
+    }
+
+    #[test]
+    fn parse_and_render() {
+        let grid = sample();
+
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid[(1, 1)], '#');
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.to_string(), "#..\n.##\n..#");
+        assert_eq!(grid.render(|&c| if c == '#' { 'X' } else { ' ' }), "X  \n XX\n  X");
+
+        let tubes = Grid::parse("| \r\n+-\r\n  \r\n", Some, "a cell").unwrap();
+        assert_eq!((tubes.width(), tubes.height()), (2, 3));
+        assert_eq!(tubes.to_string(), "| \n+-\n  ");
+    }
+
+    #[test]
+    fn parse_errors() {
+        match Grid::parse("#.\n#.#", Some, "a cell") {
+            Err(Error::Parse(e)) => assert_eq!((e.line, e.column), (2, 4)),
+            other => panic!("expected a parse error, got: {:?}", other),
+        }
+
+        match Grid::parse("#.\n#x", |c| "#.".contains(c).then_some(c), "`#` or `.`") {
+            Err(Error::Parse(e)) => assert_eq!((e.line, e.column), (2, 2)),
+            other => panic!("expected a parse error, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rows_columns_and_rectangles() {
+        let mut grid = Grid::from_fn(4, 3, |x, y| x + 10 * y);
+
+        assert_eq!(grid.row(1), &[10, 11, 12, 13]);
+        assert_eq!(grid.column(2).copied().collect::<Vec<_>>(), vec![2, 12, 22]);
+        assert_eq!(grid.rect((2, 2), (1, 1)).copied().collect::<Vec<_>>(), vec![11, 12, 21, 22]);
+
+        grid.rect_mut((0, 0), (1, 1)).for_each(|v| *v = 0);
+        assert_eq!(grid.values().filter(|&&v| v == 0).count(), 4);
+    }
+
+    #[test]
+    fn neighbors_stay_inside() {
+        let grid = Grid::new(3, 3, 0);
+
+        assert_eq!(grid.neighbors4((0, 0)).collect::<Vec<_>>(), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbors8((1, 1)).count(), 8);
+        assert_eq!(grid.neighbors8((2, 2)).count(), 3);
+    }
+
+    #[test]
+    fn regions_of_equal_cells() {
+        let regions = sample().regions();
+
+        assert_eq!(regions.len(), 4);
+        assert_eq!(regions[3], vec![(1, 1), (2, 1), (2, 2)]);
+    }
+}
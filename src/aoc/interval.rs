@@ -0,0 +1,364 @@
+//! Sets of ranges on a line and of rectangles on a plane, so that overlaps
+//! can be worked out from the coordinates instead of cell by cell.
+
+use std::ops::Range;
+
+/// The integers from `start` up to, but not including, `end`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Interval {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl Interval {
+    /// Intervals where `end` is not after `start` are empty.
+    pub fn new(start: i64, end: i64) -> Self {
+        Interval { start, end: end.max(start) }
+    }
+
+    /// The integers from `first` to `last`, both included, as most puzzles
+    /// write their ranges. The end is exclusive, so `i64::MAX` itself can
+    /// never be in an interval and a `last` of it is left out.
+    pub fn inclusive(first: i64, last: i64) -> Self {
+        Interval::new(first, last.saturating_add(1))
+    }
+
+    pub fn len(&self) -> u64 {
+        self.end.abs_diff(self.start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        self.start <= value && value < self.end
+    }
+
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        let overlap = Interval::new(self.start.max(other.start), self.end.min(other.end));
+        (!overlap.is_empty()).then_some(overlap)
+    }
+}
+
+/// A union of intervals, kept as sorted, disjoint and non-adjacent pieces.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, interval: Interval) {
+        if interval.is_empty() {
+            return;
+        }
+
+        // pieces that overlap or touch the new one are merged into it
+        let first = self.intervals.partition_point(|i| i.end < interval.start);
+        let last = self.intervals.partition_point(|i| i.start <= interval.end);
+
+        let merged = self.intervals[first..last]
+            .iter()
+            .fold(interval, |acc, i| Interval::new(acc.start.min(i.start), acc.end.max(i.end)));
+        self.intervals.splice(first..last, [merged]);
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = self.clone();
+        for &interval in &other.intervals {
+            result.insert(interval);
+        }
+
+        result
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a, b) = (self.intervals[i], other.intervals[j]);
+            intervals.extend(a.intersection(&b));
+
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = vec![];
+        for &interval in &self.intervals {
+            let mut rest = interval;
+            for cut in other.intervals.iter().filter(|c| c.intersection(&interval).is_some()) {
+                intervals.push(Interval::new(rest.start, cut.start));
+                rest = Interval::new(cut.end, rest.end);
+            }
+            intervals.push(rest);
+        }
+        intervals.retain(|i| !i.is_empty());
+
+        IntervalSet { intervals }
+    }
+
+    /// How many integers are in the set.
+    pub fn len(&self) -> u64 {
+        self.intervals.iter().map(Interval::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        let i = self.intervals.partition_point(|i| i.end <= value);
+        self.intervals.get(i).is_some_and(|i| i.contains(value))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Interval> {
+        self.intervals.iter()
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Interval>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        for interval in iter {
+            set.insert(interval);
+        }
+
+        set
+    }
+}
+
+/// An axis-aligned rectangle made of the points whose `x` is in one
+/// interval and whose `y` is in the other.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Rect {
+    pub x: Interval,
+    pub y: Interval,
+}
+
+impl Rect {
+    pub fn new(x: Interval, y: Interval) -> Self {
+        Rect { x, y }
+    }
+
+    pub fn area(&self) -> u64 {
+        self.x.len() * self.y.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty() || self.y.is_empty()
+    }
+
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        Some(Rect::new(self.x.intersection(&other.x)?, self.y.intersection(&other.y)?))
+    }
+
+    /// The part of this rectangle outside `other`, as at most four
+    /// rectangles.
+    pub fn difference(&self, other: &Rect) -> Vec<Rect> {
+        let overlap = match self.intersection(other) {
+            Some(overlap) => overlap,
+            None => return vec![*self],
+        };
+
+        // full width bands above and below the overlap, and the pieces left
+        // and right of it in between
+        [
+            Rect::new(self.x, Interval::new(self.y.start, overlap.y.start)),
+            Rect::new(self.x, Interval::new(overlap.y.end, self.y.end)),
+            Rect::new(Interval::new(self.x.start, overlap.x.start), overlap.y),
+            Rect::new(Interval::new(overlap.x.end, self.x.end), overlap.y),
+        ]
+        .into_iter()
+        .filter(|r| !r.is_empty())
+        .collect()
+    }
+}
+
+/// A union of rectangles, kept as disjoint pieces.
+#[derive(Clone, Default, Debug)]
+pub struct RectSet {
+    rects: Vec<Rect>,
+}
+
+impl RectSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, rect: Rect) {
+        let mut pieces = vec![rect];
+        for existing in &self.rects {
+            pieces = pieces.iter().flat_map(|p| p.difference(existing)).collect();
+        }
+
+        self.rects.extend(pieces.into_iter().filter(|p| !p.is_empty()));
+    }
+
+    pub fn remove(&mut self, rect: &Rect) {
+        self.rects = self.rects.iter().flat_map(|r| r.difference(rect)).collect();
+    }
+
+    pub fn union(&self, other: &RectSet) -> RectSet {
+        let mut result = self.clone();
+        for &rect in &other.rects {
+            result.insert(rect);
+        }
+
+        result
+    }
+
+    pub fn intersection(&self, other: &RectSet) -> RectSet {
+        let rects = self
+            .rects
+            .iter()
+            .flat_map(|a| other.rects.iter().filter_map(move |b| a.intersection(b)))
+            .collect();
+
+        RectSet { rects }
+    }
+
+    pub fn difference(&self, other: &RectSet) -> RectSet {
+        let mut result = self.clone();
+        for rect in &other.rects {
+            result.remove(rect);
+        }
+
+        result
+    }
+
+    pub fn area(&self) -> u64 {
+        self.rects.iter().map(Rect::area).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Rect> {
+        self.rects.iter()
+    }
+}
+
+impl FromIterator<Rect> for RectSet {
+    fn from_iter<I: IntoIterator<Item = Rect>>(iter: I) -> Self {
+        let mut set = RectSet::new();
+        for rect in iter {
+            set.insert(rect);
+        }
+
+        set
+    }
+}
+
+/// Splits a line at every given boundary, so that each segment between two
+/// consecutive boundaries can be handled as a single cell.
+///
+/// Applying operations to the few segments instead of every integer makes
+/// the work depend on how many ranges there are, not on how long they are.
+#[derive(Clone, Debug)]
+pub struct Compression {
+    boundaries: Vec<i64>,
+}
+
+impl Compression {
+    pub fn new(boundaries: impl IntoIterator<Item = i64>) -> Self {
+        let mut boundaries = boundaries.into_iter().collect::<Vec<_>>();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        Compression { boundaries }
+    }
+
+    /// Boundaries for the start and end of every interval.
+    pub fn of_intervals<'a>(intervals: impl IntoIterator<Item = &'a Interval>) -> Self {
+        Compression::new(intervals.into_iter().flat_map(|i| [i.start, i.end]))
+    }
+
+    /// The number of segments.
+    pub fn len(&self) -> usize {
+        self.boundaries.len().saturating_sub(1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn segment(&self, index: usize) -> Interval {
+        Interval::new(self.boundaries[index], self.boundaries[index + 1])
+    }
+
+    /// The indices of the segments making up `interval`, or `None` when its
+    /// start or end is not one of the boundaries.
+    pub fn segments(&self, interval: &Interval) -> Option<Range<usize>> {
+        let index = |value| self.boundaries.binary_search(&value).ok();
+
+        Some(index(interval.start)?..index(interval.end)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn set(intervals: &[(i64, i64)]) -> IntervalSet {
+        intervals.iter().map(|&(a, b)| Interval::new(a, b)).collect()
+    }
+
+    #[test]
+    fn intervals_merge() {
+        let s = set(&[(5, 8), (0, 2), (2, 3), (7, 10)]);
+
+        assert_eq!(s.iter().copied().collect::<Vec<_>>(), vec![Interval::new(0, 3), Interval::new(5, 10)]);
+        assert_eq!(s.len(), 8);
+        assert!(s.contains(9) && !s.contains(4));
+    }
+
+    #[test]
+    fn interval_set_arithmetic() {
+        let a = set(&[(0, 10), (20, 30)]);
+        let b = set(&[(5, 25)]);
+
+        assert_eq!(a.union(&b), set(&[(0, 30)]));
+        assert_eq!(a.intersection(&b), set(&[(5, 10), (20, 25)]));
+        assert_eq!(a.difference(&b), set(&[(0, 5), (25, 30)]));
+        assert_eq!(b.difference(&a), set(&[(10, 20)]));
+    }
+
+    #[test]
+    fn rect_set_area() {
+        let square = |x, y, side| Rect::new(Interval::new(x, x + side), Interval::new(y, y + side));
+
+        let mut s = [square(0, 0, 4), square(2, 2, 4)].into_iter().collect::<RectSet>();
+        assert_eq!(s.area(), 16 + 16 - 4);
+
+        let other = [square(1, 1, 2)].into_iter().collect::<RectSet>();
+        assert_eq!(s.intersection(&other).area(), 4);
+        assert_eq!(s.difference(&other).area(), 28 - 4);
+
+        s.remove(&square(0, 0, 6));
+        assert_eq!(s.area(), 0);
+    }
+
+    #[test]
+    fn compressed_segments() {
+        let c = Compression::of_intervals(&[Interval::inclusive(0, 9), Interval::inclusive(5, 99)]);
+
+        assert_eq!(c.len(), 3);
+        assert_eq!(c.segments(&Interval::inclusive(5, 9)), Some(1..2));
+        assert_eq!(c.segments(&Interval::inclusive(5, 10)), None);
+        assert_eq!(c.segment(2), Interval::new(10, 100));
+    }
+
+    #[test]
+    fn inclusive_at_the_limits() {
+        assert_eq!(Interval::inclusive(i64::MAX - 1, i64::MAX), Interval::new(i64::MAX - 1, i64::MAX));
+        assert_eq!(Interval::inclusive(i64::MIN, i64::MAX).len(), u64::MAX);
+    }
+}
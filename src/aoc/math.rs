@@ -0,0 +1,338 @@
+//! Number theory helpers. They work on any primitive integer type and
+//! return `None` instead of overflowing, so that a result that does not fit
+//! is never silently wrong.
+
+use std::fmt;
+
+pub trait Int: Copy + Ord + fmt::Debug {
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+    fn checked_div(self, other: Self) -> Option<Self>;
+    fn checked_rem_euclid(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! integers {
+    ($($t:ty),*) => {
+        $(
+            impl Int for $t {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$t>::checked_add(self, other)
+                }
+
+                fn checked_sub(self, other: Self) -> Option<Self> {
+                    <$t>::checked_sub(self, other)
+                }
+
+                fn checked_mul(self, other: Self) -> Option<Self> {
+                    <$t>::checked_mul(self, other)
+                }
+
+                fn checked_div(self, other: Self) -> Option<Self> {
+                    <$t>::checked_div(self, other)
+                }
+
+                fn checked_rem_euclid(self, other: Self) -> Option<Self> {
+                    <$t>::checked_rem_euclid(self, other)
+                }
+            }
+        )*
+    };
+}
+
+integers!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+fn abs<T: Int>(n: T) -> Option<T> {
+    if n < T::ZERO {
+        T::ZERO.checked_sub(n)
+    } else {
+        Some(n)
+    }
+}
+
+/// The greatest common divisor of the absolute values. `gcd(0, 0)` is 0.
+pub fn gcd<T: Int>(a: T, b: T) -> Option<T> {
+    let (mut a, mut b) = (abs(a)?, abs(b)?);
+    while b != T::ZERO {
+        (a, b) = (b, a.checked_rem_euclid(b)?);
+    }
+
+    Some(a)
+}
+
+/// The least common multiple of the absolute values, 0 if either is 0.
+pub fn lcm<T: Int>(a: T, b: T) -> Option<T> {
+    if a == T::ZERO || b == T::ZERO {
+        return Some(T::ZERO);
+    }
+
+    abs(a)?.checked_div(gcd(a, b)?)?.checked_mul(abs(b)?)
+}
+
+/// `(g, x, y)` such that `a * x + b * y = g`, where `g` is `gcd(a, b)`.
+/// Unsigned types often need a negative `x` or `y`, so this is mostly
+/// useful with signed ones.
+pub fn extended_gcd<T: Int>(a: T, b: T) -> Option<(T, T, T)> {
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (T::ONE, T::ZERO);
+    let (mut y0, mut y1) = (T::ZERO, T::ONE);
+
+    while r1 != T::ZERO {
+        let q = r0.checked_div(r1)?;
+        (r0, r1) = (r1, r0.checked_sub(q.checked_mul(r1)?)?);
+        (x0, x1) = (x1, x0.checked_sub(q.checked_mul(x1)?)?);
+        (y0, y1) = (y1, y0.checked_sub(q.checked_mul(y1)?)?);
+    }
+
+    if r0 < T::ZERO {
+        let negate = |n: T| T::ZERO.checked_sub(n);
+        return Some((negate(r0)?, negate(x0)?, negate(y0)?));
+    }
+
+    Some((r0, x0, y0))
+}
+
+// (a + b) % m for a and b already below m, without overflowing.
+fn add_mod<T: Int>(a: T, b: T, m: T) -> Option<T> {
+    let room = m.checked_sub(b)?;
+    if a >= room {
+        a.checked_sub(room)
+    } else {
+        a.checked_add(b)
+    }
+}
+
+// (a - b) % m for a and b already below m, without going negative.
+fn sub_mod<T: Int>(a: T, b: T, m: T) -> Option<T> {
+    if a >= b {
+        a.checked_sub(b)
+    } else {
+        add_mod(a, m.checked_sub(b)?, m)
+    }
+}
+
+/// `(a * b) mod m`, computed so that it works even when `a * b` does not
+/// fit in `T`.
+pub fn mul_mod<T: Int>(a: T, b: T, m: T) -> Option<T> {
+    let two = T::ONE.checked_add(T::ONE)?;
+    let mut a = a.checked_rem_euclid(m)?;
+    let mut b = b.checked_rem_euclid(m)?;
+
+    if let Some(product) = a.checked_mul(b) {
+        return product.checked_rem_euclid(m);
+    }
+
+    // double and add, one bit of `b` at a time
+    let mut result = T::ZERO;
+    while b != T::ZERO {
+        if b.checked_rem_euclid(two)? == T::ONE {
+            result = add_mod(result, a, m)?;
+        }
+        a = add_mod(a, a, m)?;
+        b = b.checked_div(two)?;
+    }
+
+    Some(result)
+}
+
+/// `base` to the power of `exp`, modulo `m`. `m` must be positive and `exp`
+/// must not be negative.
+pub fn mod_pow<T: Int>(base: T, exp: T, m: T) -> Option<T> {
+    if m <= T::ZERO || exp < T::ZERO {
+        return None;
+    }
+
+    let two = T::ONE.checked_add(T::ONE)?;
+    let (mut base, mut exp) = (base.checked_rem_euclid(m)?, exp);
+    let mut result = T::ONE.checked_rem_euclid(m)?;
+
+    while exp != T::ZERO {
+        if exp.checked_rem_euclid(two)? == T::ONE {
+            result = mul_mod(result, base, m)?;
+        }
+        base = mul_mod(base, base, m)?;
+        exp = exp.checked_div(two)?;
+    }
+
+    Some(result)
+}
+
+/// The `x` in `0..m` such that `a * x mod m` is 1, if `a` and `m` are
+/// coprime.
+pub fn mod_inverse<T: Int>(a: T, m: T) -> Option<T> {
+    if m <= T::ZERO {
+        return None;
+    }
+
+    // extended Euclid, keeping the coefficient of `a` modulo `m` so that it
+    // also works for unsigned types
+    let (mut r0, mut r1) = (m, a.checked_rem_euclid(m)?);
+    let (mut t0, mut t1) = (T::ZERO, T::ONE.checked_rem_euclid(m)?);
+
+    while r1 != T::ZERO {
+        let q = r0.checked_div(r1)?;
+        (r0, r1) = (r1, r0.checked_sub(q.checked_mul(r1)?)?);
+        (t0, t1) = (t1, sub_mod(t0, mul_mod(q, t1, m)?, m)?);
+    }
+
+    (r0 == T::ONE).then_some(t0)
+}
+
+/// Solves a system of congruences `x mod m = r`, given as `(r, m)` pairs
+/// with positive moduli that do not need to be coprime.
+///
+/// Returns the smallest non-negative `x` together with the least common
+/// multiple of the moduli, as every other solution differs from `x` by a
+/// multiple of it, or `None` if there is no solution.
+pub fn crt<T: Int>(congruences: &[(T, T)]) -> Option<(T, T)> {
+    let mut x = T::ZERO;
+    let mut modulus = T::ONE;
+
+    for &(r, m) in congruences {
+        if m <= T::ZERO {
+            return None;
+        }
+        let r = r.checked_rem_euclid(m)?;
+
+        // find k such that x + modulus * k = r (mod m)
+        let g = gcd(modulus, m)?;
+        let diff = sub_mod(r, x.checked_rem_euclid(m)?, m)?;
+        if diff.checked_rem_euclid(g)? != T::ZERO {
+            return None;
+        }
+
+        let step = m.checked_div(g)?;
+        let inverse = mod_inverse(modulus.checked_div(g)?, step)?;
+        let k = mul_mod(diff.checked_div(g)?, inverse, step)?;
+
+        let combined = modulus.checked_mul(step)?;
+        x = x.checked_add(modulus.checked_mul(k)?)?.checked_rem_euclid(combined)?;
+        modulus = combined;
+    }
+
+    Some((x, modulus))
+}
+
+/// For every number up to `limit`, whether it is prime.
+pub fn sieve(limit: usize) -> Vec<bool> {
+    let mut is_prime = vec![true; limit + 1];
+    is_prime[0] = false;
+    if limit >= 1 {
+        is_prime[1] = false;
+    }
+
+    let mut n = 2;
+    while n * n <= limit {
+        if is_prime[n] {
+            for multiple in (n * n..=limit).step_by(n) {
+                is_prime[multiple] = false;
+            }
+        }
+        n += 1;
+    }
+
+    is_prime
+}
+
+/// Every prime up to `limit`, in increasing order.
+pub fn primes_up_to(limit: usize) -> Vec<usize> {
+    sieve(limit)
+        .into_iter()
+        .enumerate()
+        .filter_map(|(n, prime)| prime.then_some(n))
+        .collect()
+}
+
+/// Every positive divisor of `n`, in increasing order. `n` must be positive.
+pub fn divisors<T: Int>(n: T) -> Vec<T> {
+    let mut small = vec![];
+    let mut large = vec![];
+
+    let mut d = T::ONE;
+    while let Some(square) = d.checked_mul(d).filter(|&s| s <= n) {
+        if n.checked_rem_euclid(d) == Some(T::ZERO) {
+            small.push(d);
+            if square != n {
+                large.extend(n.checked_div(d));
+            }
+        }
+
+        match d.checked_add(T::ONE) {
+            Some(next) => d = next,
+            None => break,
+        }
+    }
+
+    small.extend(large.into_iter().rev());
+    small
+}
+
+/// The sum of the divisors of every number up to `limit`, for puzzles that
+/// need it for many numbers at once.
+pub fn divisor_sums(limit: usize) -> Vec<u64> {
+    let mut sums = vec![0u64; limit + 1];
+    for d in 1..=limit {
+        for multiple in (d..=limit).step_by(d) {
+            sums[multiple] += d as u64;
+        }
+    }
+
+    sums
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gcd_and_lcm() {
+        assert_eq!(gcd(12, 18), Some(6));
+        assert_eq!(gcd(-12i32, 18), Some(6));
+        assert_eq!(gcd(0u8, 0), Some(0));
+        assert_eq!(gcd(i8::MIN, 0), None);
+        assert_eq!(lcm(4u64, 6), Some(12));
+        assert_eq!(lcm(u8::MAX, 2), None);
+    }
+
+    #[test]
+    fn extended_euclid() {
+        let (g, x, y) = extended_gcd(240i64, 46).unwrap();
+
+        assert_eq!(g, 2);
+        assert_eq!(240 * x + 46 * y, 2);
+    }
+
+    #[test]
+    fn modular_arithmetic() {
+        assert_eq!(mod_inverse(3u32, 11), Some(4));
+        assert_eq!(mod_inverse(-3i32, 11), Some(7));
+        assert_eq!(mod_inverse(6u32, 9), None);
+
+        assert_eq!(mod_pow(2u32, 10, 1000), Some(24));
+        assert_eq!(mod_pow(5u8, 0, 1), Some(0));
+        assert_eq!(mod_pow(u64::MAX - 1, 2, u64::MAX), Some(1));
+        assert_eq!(mul_mod(u64::MAX - 1, u64::MAX - 1, u64::MAX), Some(1));
+    }
+
+    #[test]
+    fn chinese_remainder() {
+        assert_eq!(crt(&[(2u32, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(1i64, 4), (3, 6)]), Some((9, 12)));
+        assert_eq!(crt(&[(0u8, 4), (1, 6)]), None);
+        assert_eq!(crt::<u32>(&[]), Some((0, 1)));
+    }
+
+    #[test]
+    fn primes_and_divisors() {
+        assert_eq!(primes_up_to(30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert_eq!(divisors(36u32), vec![1, 2, 3, 4, 6, 9, 12, 18, 36]);
+        assert_eq!(divisors(u8::MAX), vec![1, 3, 5, 15, 17, 51, 85, 255]);
+        assert_eq!(divisor_sums(9)[6..], [12, 8, 15, 13]);
+    }
+}
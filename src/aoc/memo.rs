@@ -0,0 +1,177 @@
+//! Memoized evaluation of values that depend on other values, like the
+//! signal on a wire depending on the wires before it.
+//!
+//! Both ways of evaluating report a cycle in the dependencies as
+//! `Error::Cycle` instead of looping forever. [`Memo::recursive`] is the
+//! most natural to write but uses the call stack, [`Memo::evaluate`] keeps
+//! its own stack so that long chains of dependencies cannot overflow it.
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+
+use crate::aoc::{Error, Result};
+
+/// How [`Memo::recursive`] gets the value of another key.
+pub type Recurse<'r, K, V> = &'r mut dyn FnMut(K) -> Result<V>;
+
+/// Computes the value of a key for [`Memo::recursive`].
+pub type Compute<'f, K, V> = dyn Fn(&K, Recurse<K, V>) -> Result<V> + 'f;
+
+/// The values computed so far, by key.
+pub struct Memo<K, V> {
+    values: HashMap<K, V>,
+    path: Vec<K>,
+}
+
+impl<K, V> Default for Memo<K, V> {
+    fn default() -> Self {
+        Memo { values: HashMap::new(), path: vec![] }
+    }
+}
+
+// The keys from where `key` was first reached back to `key` itself.
+fn cycle<K: PartialEq + Display>(path: &[K], key: &K) -> Error {
+    let start = path.iter().position(|k| k == key).unwrap_or(0);
+    Error::Cycle(path[start..].iter().chain([key]).map(|k| k.to_string()).collect())
+}
+
+impl<K: Hash + Eq + Clone + Display, V: Clone> Memo<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.values.get(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The value for `key`, computed by `f` unless it already was. `f` gets
+    /// the key and a function to call for the value of another key.
+    pub fn recursive(
+        &mut self,
+        key: K,
+        f: &Compute<'_, K, V>,
+    ) -> Result<V> {
+        if let Some(value) = self.values.get(&key) {
+            return Ok(value.clone());
+        }
+        if self.path.contains(&key) {
+            return Err(cycle(&self.path, &key));
+        }
+
+        self.path.push(key.clone());
+        let value = f(&key, &mut |other| self.recursive(other, f));
+        self.path.pop();
+
+        let value = value?;
+        self.values.insert(key, value.clone());
+        Ok(value)
+    }
+
+    /// The value for `key`, computed without recursion. `dependencies` lists
+    /// the keys a value needs, and `compute` gets their values in the same
+    /// order. Values are computed after all of their dependencies, so
+    /// `compute` is also called in that order.
+    pub fn evaluate(
+        &mut self,
+        key: K,
+        mut dependencies: impl FnMut(&K) -> Result<Vec<K>>,
+        mut compute: impl FnMut(&K, &[V]) -> Result<V>,
+    ) -> Result<V> {
+        if let Some(value) = self.values.get(&key) {
+            return Ok(value.clone());
+        }
+
+        // each frame is a key, its dependencies and how many of them were
+        // already looked at, `on_stack` has where each key is in it
+        let mut stack = vec![(key.clone(), dependencies(&key)?, 0)];
+        let mut on_stack = HashMap::from([(key.clone(), 0)]);
+
+        while let Some((current, needed, next)) = stack.last_mut() {
+            if let Some(dependency) = needed.get(*next).cloned() {
+                *next += 1;
+                if self.values.contains_key(&dependency) {
+                    continue;
+                }
+
+                if let Some(&start) = on_stack.get(&dependency) {
+                    let path = stack[start..].iter().map(|(k, _, _)| k.clone()).collect::<Vec<_>>();
+                    return Err(cycle(&path, &dependency));
+                }
+
+                let needs = dependencies(&dependency)?;
+                on_stack.insert(dependency.clone(), stack.len());
+                stack.push((dependency, needs, 0));
+                continue;
+            }
+
+            let inputs = needed.iter().map(|k| self.values[k].clone()).collect::<Vec<_>>();
+            let value = compute(current, &inputs)?;
+            let (current, _, _) = stack.pop().unwrap();
+            on_stack.remove(&current);
+            self.values.insert(current, value);
+        }
+
+        Ok(self.values[&key].clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fibonacci(n: u64, next: Recurse<u64, u64>) -> Result<u64> {
+        match n {
+            0 | 1 => Ok(n),
+            n => Ok(next(n - 1)? + next(n - 2)?),
+        }
+    }
+
+    #[test]
+    fn recursive_values_are_computed_once() {
+        let mut memo = Memo::new();
+
+        assert_eq!(memo.recursive(80, &|&n, next| fibonacci(n, next)).unwrap(), 23_416_728_348_467_685);
+        assert_eq!(memo.len(), 81);
+    }
+
+    #[test]
+    fn long_chains_do_not_overflow() {
+        let mut memo = Memo::new();
+        let length = 200_000u64;
+        let value = memo.evaluate(
+            length,
+            |&n| Ok(if n == 0 { vec![] } else { vec![n - 1] }),
+            |_, before| Ok(before.first().map_or(0, |v| v + 1)));
+
+        assert_eq!(value.unwrap(), length);
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let next = |k: &&str| match *k {
+            "a" => "b",
+            "b" => "c",
+            _ => "b",
+        };
+
+        let mut memo = Memo::<&str, u8>::new();
+        match memo.evaluate("a", |k| Ok(vec![next(k)]), |_, _| Ok(0)) {
+            Err(Error::Cycle(keys)) => assert_eq!(keys, ["b", "c", "b"]),
+            other => panic!("expected a cycle, got: {:?}", other),
+        }
+
+        match memo.recursive("a", &|k, get| get(next(k))) {
+            Err(e @ Error::Cycle(_)) => assert_eq!(e.to_string(), "the input has a cycle: b -> c -> b"),
+            other => panic!("expected a cycle, got: {:?}", other),
+        }
+    }
+}
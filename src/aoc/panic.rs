@@ -0,0 +1,81 @@
+use crate::aoc::{Error, Result};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+thread_local! {
+    static CATCHING: Cell<bool> = const { Cell::new(false) };
+    static LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+static HOOK: Once = Once::new();
+
+/// Runs `f` and turns a panic inside it into `Error::Panic`, so a bug in
+/// one solution does not abort everything else that is running.
+///
+/// While `f` runs the default panic message is not printed, the message and
+/// location are carried by the error instead.
+pub fn catch<T>(f: impl FnOnce() -> Result<T>) -> Result<T> {
+    install_hook();
+
+    let was_catching = CATCHING.with(|c| c.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    CATCHING.with(|c| c.set(was_catching));
+
+    result.unwrap_or_else(|payload| {
+        Err(Error::Panic {
+            message: message(payload.as_ref()),
+            location: LOCATION.with(|l| l.borrow_mut().take()),
+        })
+    })
+}
+
+fn install_hook() {
+    HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if CATCHING.with(|c| c.get()) {
+                let location = info.location().map(|l| l.to_string());
+                LOCATION.with(|l| *l.borrow_mut() = location);
+            } else {
+                previous(info);
+            }
+        }));
+    });
+}
+
+fn message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn no_panic() {
+        assert_eq!(catch(|| Ok(1)).unwrap(), 1);
+        assert!(matches!(catch(|| -> Result<u8> { Err(Error::InvalidInput) }), Err(Error::InvalidInput)));
+    }
+
+    #[test]
+    fn panic_is_an_error() {
+        let line = line!() + 1;
+        let result: Result<u8> = catch(|| panic!("boom {}", 42));
+
+        match result {
+            Err(Error::Panic { message, location }) => {
+                assert_eq!(message, "boom 42");
+                assert!(location.unwrap().starts_with(&format!("{}:{}:", file!(), line)));
+            }
+            _ => panic!("expected a panic error"),
+        }
+    }
+}
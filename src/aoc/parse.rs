@@ -0,0 +1,375 @@
+use std::str::FromStr;
+
+use crate::aoc::{Error, ParseError, Result};
+
+pub use aoc_derive::FromLine;
+
+/// Reads the tokens of a single line from left to right.
+///
+/// Every method skips the spaces before the token it reads, and on failure
+/// returns a parse error pointing at the offending text inside the whole
+/// input, so the caller only has to describe the shape of the line.
+pub struct Cursor<'a> {
+    input: &'a str,
+    rest: &'a str,
+    line: Option<usize>,
+}
+
+impl<'a> Cursor<'a> {
+    /// `line` must be a slice of `input`.
+    pub fn new(input: &'a str, line: &'a str) -> Self {
+        Cursor { input, rest: line, line: None }
+    }
+
+    /// A cursor over a line that was already split from the input, whose
+    /// errors point at the line with this number.
+    ///
+    /// Errors are worked out from the line alone, which keeps them cheap to
+    /// build however long the input is. That matters as some of them are
+    /// thrown away, for example by [`Cursor::optional`].
+    pub fn on_line(line: &'a str, number: usize) -> Self {
+        Cursor { input: line, rest: line, line: Some(number) }
+    }
+
+    /// A parse error at `span`, which must be a slice of the input.
+    pub fn error(&self, span: &str, expected: impl Into<String>) -> Error {
+        match (Error::parse(self.input, span, expected), self.line) {
+            (Error::Parse(e), Some(line)) => Error::Parse(ParseError { line, ..e }),
+            (e, _) => e,
+        }
+    }
+
+    /// Reads exactly `text`, such as `,` or `->`.
+    pub fn literal(&mut self, text: &str) -> Result<&'a str> {
+        self.skip_whitespace();
+
+        match self.rest.strip_prefix(text) {
+            Some(rest) => Ok(self.advance(rest)),
+            None => Err(self.error(self.next_token(), format!("`{}`", text))),
+        }
+    }
+
+    /// Reads one or more words, e.g. `"turn on"`, each of them followed by
+    /// something that is not part of a word. Any amount of space is allowed
+    /// between the words.
+    pub fn keyword(&mut self, words: &str) -> Result<&'a str> {
+        let start = self.rest;
+
+        for word in words.split_whitespace() {
+            self.skip_whitespace();
+
+            let matched = match self.rest.strip_prefix(word) {
+                Some(rest) if !rest.starts_with(is_word_char) => rest,
+                _ => {
+                    let error = self.error(self.next_token(), format!("`{}`", words));
+                    self.rest = start;
+                    return Err(error);
+                }
+            };
+            self.rest = matched;
+        }
+
+        let start = start.trim_start();
+        Ok(&start[..start.len() - self.rest.len()])
+    }
+
+    /// Reads a name made of letters, digits and underscores.
+    pub fn identifier(&mut self) -> Result<&'a str> {
+        self.skip_whitespace();
+
+        let len = self.rest.find(|c| !is_word_char(c)).unwrap_or(self.rest.len());
+        if len == 0 {
+            return Err(self.error(self.next_token(), "a name"));
+        }
+
+        Ok(self.advance(&self.rest[len..]))
+    }
+
+    /// Reads a decimal integer with an optional sign. Numbers that do not
+    /// fit in `T` are rejected.
+    pub fn integer<T: FromStr>(&mut self) -> Result<T> {
+        self.integer_span().map(|(n, _)| n)
+    }
+
+    /// Like [`Cursor::integer`], but also returns the text of the number so
+    /// that the caller can report errors about its value.
+    pub fn integer_span<T: FromStr>(&mut self) -> Result<(T, &'a str)> {
+        self.skip_whitespace();
+
+        let sign = if self.rest.starts_with(['-', '+']) { 1 } else { 0 };
+        let len = self.rest[sign..]
+            .find(|c: char| !c.is_ascii_digit())
+            .map_or(self.rest.len(), |i| i + sign);
+
+        let text = &self.rest[..len];
+        match text.parse::<T>() {
+            Ok(n) if len > sign => {
+                self.rest = &self.rest[len..];
+                Ok((n, text))
+            }
+            _ => Err(self.error(self.next_token(), "a number")),
+        }
+    }
+
+    /// Reads two items separated by `separator`, like the `3,4` of a
+    /// coordinate pair.
+    pub fn pair<T>(&mut self, separator: &str, mut item: impl FnMut(&mut Self) -> Result<T>) -> Result<(T, T)> {
+        let first = item(self)?;
+        self.literal(separator)?;
+        let second = item(self)?;
+
+        Ok((first, second))
+    }
+
+    /// Reads one or more items separated by `separator`.
+    pub fn separated<T>(&mut self, separator: &str, mut item: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let mut items = vec![item(self)?];
+        while self.optional(|c| c.literal(separator)).is_some() {
+            items.push(item(self)?);
+        }
+
+        Ok(items)
+    }
+
+    /// Reads the first of `keywords` found and returns its value.
+    pub fn one_of<T: Clone>(&mut self, keywords: &[(&str, T)]) -> Result<T> {
+        for (keyword, value) in keywords {
+            if self.optional(|c| c.keyword(keyword)).is_some() {
+                return Ok(value.clone());
+            }
+        }
+
+        let expected = keywords
+            .iter()
+            .map(|(keyword, _)| format!("`{}`", keyword))
+            .collect::<Vec<_>>();
+        let expected = match expected.split_last() {
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+            None => "nothing".to_owned(),
+        };
+
+        Err(self.error(self.next_token(), expected))
+    }
+
+    /// Runs `parse`, putting the cursor back where it was if it fails.
+    pub fn optional<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T>) -> Option<T> {
+        let start = self.rest;
+        match parse(self) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.rest = start;
+                None
+            }
+        }
+    }
+
+    /// Checks that nothing but spaces is left on the line.
+    pub fn end(&mut self) -> Result<()> {
+        self.skip_whitespace();
+
+        if self.rest.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error(self.next_token(), "the end of the line"))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    // Moves to `rest` and returns what was skipped.
+    fn advance(&mut self, rest: &'a str) -> &'a str {
+        let skipped = &self.rest[..self.rest.len() - rest.len()];
+        self.rest = rest;
+        skipped
+    }
+
+    // The text to point at when the next token is not the expected one: a
+    // whole word, a single symbol, or the end of the line.
+    fn next_token(&self) -> &'a str {
+        let rest = self.rest.trim_start();
+
+        let len = match rest.chars().next() {
+            Some(c) if is_word_char(c) => rest.find(|c| !is_word_char(c)).unwrap_or(rest.len()),
+            Some(c) => c.len_utf8(),
+            None => 0,
+        };
+
+        &rest[..len]
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Parses every non-blank line of `input` as a whole record. `record` reads
+/// the line through a [`Cursor`], and anything left after it is an error.
+pub fn lines<'a, T>(
+    input: &'a str,
+    record: impl Fn(&mut Cursor<'a>) -> Result<T> + 'a,
+) -> impl Iterator<Item = Result<T>> + 'a {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(move |(i, line)| {
+            let mut cursor = Cursor::on_line(line, i + 1);
+            let value = record(&mut cursor)?;
+            cursor.end()?;
+
+            Ok(value)
+        })
+}
+
+/// A record that takes up a whole line. It is usually derived, see
+/// [`aoc_derive::FromLine`].
+pub trait FromLine<'a>: Sized {
+    fn from_line(cursor: &mut Cursor<'a>) -> Result<Self>;
+}
+
+/// Parses every non-blank line of `input` as a `T`.
+pub fn records<'a, T: FromLine<'a> + 'a>(input: &'a str) -> impl Iterator<Item = Result<T>> + 'a {
+    lines(input, T::from_line)
+}
+
+/// A value that fills one placeholder of a `#[line(...)]` template.
+pub trait Token<'a>: Sized {
+    fn read(cursor: &mut Cursor<'a>) -> Result<Self>;
+}
+
+macro_rules! integer_tokens {
+    ($($t:ty),*) => {
+        $(
+            impl<'a> Token<'a> for $t {
+                fn read(cursor: &mut Cursor<'a>) -> Result<Self> {
+                    cursor.integer()
+                }
+            }
+        )*
+    };
+}
+
+integer_tokens!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+impl<'a> Token<'a> for &'a str {
+    fn read(cursor: &mut Cursor<'a>) -> Result<Self> {
+        cursor.identifier()
+    }
+}
+
+impl<'a> Token<'a> for String {
+    fn read(cursor: &mut Cursor<'a>) -> Result<Self> {
+        cursor.identifier().map(str::to_owned)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn error_column<T: std::fmt::Debug>(result: Result<T>) -> usize {
+        match result {
+            Err(Error::Parse(e)) => e.column,
+            other => panic!("expected a parse error, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn whole_line_record() {
+        let input = "London to Dublin = 464\n\nLondon to Belfast = 518\n";
+        let records = lines(input, |c| {
+            let from = c.identifier()?;
+            c.keyword("to")?;
+            let to = c.identifier()?;
+            c.literal("=")?;
+            Ok((from, to, c.integer::<u32>()?))
+        })
+        .collect::<Result<Vec<_>>>()
+        .unwrap();
+
+        assert_eq!(records, vec![("London", "Dublin", 464), ("London", "Belfast", 518)]);
+    }
+
+    #[test]
+    fn error_points_at_bad_token() {
+        let input = "toggle 1,x through 2,2";
+
+        let mut cursor = Cursor::new(input, input);
+        cursor.keyword("toggle").unwrap();
+        let pair = cursor.pair(",", |c| c.integer::<u32>());
+
+        assert_eq!(error_column(pair), 10);
+    }
+
+    #[test]
+    fn keyword_needs_word_boundary() {
+        let input = "tomorrow";
+        let mut cursor = Cursor::new(input, input);
+
+        assert!(cursor.keyword("to").is_err());
+        assert_eq!(cursor.identifier().unwrap(), "tomorrow");
+    }
+
+    #[test]
+    fn integer_out_of_range() {
+        let input = "300";
+        let mut cursor = Cursor::new(input, input);
+
+        assert_eq!(error_column(cursor.integer::<u8>()), 1);
+        assert_eq!(cursor.integer::<i16>().unwrap(), 300);
+    }
+
+    #[test]
+    fn separated_list() {
+        let input = "2x3x4 rest";
+        let mut cursor = Cursor::new(input, input);
+
+        assert_eq!(cursor.separated("x", |c| c.integer::<u32>()).unwrap(), vec![2, 3, 4]);
+        assert_eq!(error_column(cursor.end()), 7);
+    }
+
+    #[test]
+    fn one_of_keywords() {
+        let input = "turn off";
+        let mut cursor = Cursor::new(input, input);
+        let choices = [("turn on", 1), ("turn off", 2), ("toggle", 3)];
+
+        assert_eq!(cursor.one_of(&choices).unwrap(), 2);
+
+        let input = "turn up";
+        let mut cursor = Cursor::new(input, input);
+        match cursor.one_of(&choices) {
+            Err(Error::Parse(e)) => assert_eq!(e.expected, "`turn on`, `turn off` or `toggle`"),
+            other => panic!("expected a parse error, got: {:?}", other),
+        }
+    }
+
+    #[derive(FromLine, Debug, PartialEq)]
+    #[line("{name} can fly {speed} km/s for {time} seconds.")]
+    struct Reindeer {
+        name: String,
+        speed: u32,
+        time: u32,
+    }
+
+    #[test]
+    fn derived_from_str() {
+        let reindeer = "Comet can fly 14 km/s for 10 seconds.".parse::<Reindeer>().unwrap();
+
+        assert_eq!(reindeer, Reindeer { name: "Comet".to_owned(), speed: 14, time: 10 });
+    }
+
+    #[test]
+    fn derived_error_position() {
+        let input = "Comet can fly 14 km/s for 10 seconds.\nDancer can fly x km/s for 11 seconds.";
+        let error = records::<Reindeer>(input).find_map(|r| r.err());
+
+        match error {
+            Some(Error::Parse(e)) => assert_eq!((e.line, e.column, e.expected.as_str()), (2, 16, "a number")),
+            other => panic!("expected a parse error, got: {:?}", other),
+        }
+    }
+}
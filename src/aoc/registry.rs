@@ -0,0 +1,101 @@
+//! Solutions declared with the `#[aoc]` attribute instead of a hand-written
+//! `DaySolution` and registry entry.
+//!
+//! ```ignore
+//! #[aoc_parser(year = 2015, day = 2)]
+//! fn parse_input(input: &str) -> Result<Vec<Present>> { ... }
+//!
+//! #[aoc(year = 2015, day = 2, part = 1)]
+//! fn paper(presents: &[Present]) -> Result<u32> { ... }
+//! ```
+//!
+//! Each attribute adds an [`Entry`] to a list collected when the program
+//! starts, which the binary looks at for days missing from its own
+//! registry. [`Registered`] gives the two parts of a day as a
+//! `DaySolution`, for the test helpers.
+
+use std::fmt::Display;
+
+use crate::aoc::{DaySolution, Error, Result};
+
+pub use aoc_derive::{aoc, aoc_parser};
+#[doc(hidden)]
+pub use inventory;
+
+pub type Solve = fn(&str) -> Result<Box<dyn Display>>;
+
+pub struct Entry {
+    pub year: u16,
+    pub day: u8,
+    pub part: u8,
+    pub solve: Solve,
+}
+
+inventory::collect!(Entry);
+
+/// Parses the input of a day for its `#[aoc]` functions that take it
+/// already parsed. Implemented by `#[aoc_parser]` on [`Input`], once for
+/// every day that has a parser.
+pub trait Parser<const YEAR: u16, const DAY: u8> {
+    type Output;
+
+    fn parse(input: &str) -> Result<Self::Output>;
+}
+
+pub struct Input;
+
+pub fn entries() -> impl Iterator<Item = &'static Entry> {
+    inventory::iter::<Entry>.into_iter()
+}
+
+pub fn find(year: u16, day: u8, part: u8) -> Option<&'static Entry> {
+    entries().find(|e| (e.year, e.day, e.part) == (year, day, part))
+}
+
+/// Both parts of a registered day, with their results as text.
+pub struct Registered {
+    pub year: u16,
+    pub day: u8,
+}
+
+impl Registered {
+    pub fn new(year: u16, day: u8) -> Self {
+        Registered { year, day }
+    }
+
+    fn solve(&self, part: u8, input: &str) -> Result<String> {
+        let entry = find(self.year, self.day, part).ok_or(Error::ResultNotFound)?;
+        (entry.solve)(input).map(|result| result.to_string())
+    }
+}
+
+impl DaySolution<String> for Registered {
+    fn solve_part1(&self, input: &str) -> Result<String> {
+        self.solve(1, input)
+    }
+
+    fn solve_part2(&self, input: &str) -> Result<String> {
+        self.solve(2, input)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parts_are_registered_once() {
+        let mut keys = entries().map(|e| (e.year, e.day, e.part)).collect::<Vec<_>>();
+        let count = keys.len();
+        keys.sort();
+        keys.dedup();
+
+        assert_eq!(keys.len(), count);
+        assert!(keys.iter().all(|&(_, day, part)| (1..=25).contains(&day) && (1..=2).contains(&part)));
+    }
+
+    #[test]
+    fn missing_parts() {
+        assert!(matches!(Registered::new(1999, 1).solve_part1(""), Err(Error::ResultNotFound)));
+    }
+}
@@ -0,0 +1,118 @@
+/// Small, seedable pseudo random number generator (SplitMix64).
+///
+/// It is not suitable for anything security related, but it is fast,
+/// deterministic for a given seed and has no external dependencies, which
+/// is all that is needed to build reproducible puzzle inputs.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..bound`. `bound` must be greater than zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "bound must be greater than zero");
+
+        // rejection sampling to avoid modulo bias
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let n = self.next_u64();
+            if n < zone {
+                return n % bound;
+            }
+        }
+    }
+
+    /// Returns a number in the inclusive range `low..=high`.
+    pub fn range(&mut self, low: i64, high: i64) -> i64 {
+        assert!(low <= high, "invalid range {}..={}", low, high);
+
+        let span = high.abs_diff(low);
+        if span == u64::MAX {
+            return self.next_u64() as i64;
+        }
+
+        low.wrapping_add(self.below(span + 1) as i64)
+    }
+
+    pub fn index(&mut self, len: usize) -> usize {
+        self.below(len as u64) as usize
+    }
+
+    pub fn chance(&mut self, numerator: u64, denominator: u64) -> bool {
+        self.below(denominator) < numerator
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.index(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.index(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut rng1 = Rng::new(42);
+        let mut rng2 = Rng::new(42);
+
+        for _ in 0..100 {
+            assert_eq!(rng1.next_u64(), rng2.next_u64());
+        }
+    }
+
+    #[test]
+    fn different_seed_different_sequence() {
+        let mut rng1 = Rng::new(1);
+        let mut rng2 = Rng::new(2);
+
+        let seq1 = (0..10).map(|_| rng1.next_u64()).collect::<Vec<_>>();
+        let seq2 = (0..10).map(|_| rng2.next_u64()).collect::<Vec<_>>();
+        assert_ne!(seq1, seq2);
+    }
+
+    #[test]
+    fn range_is_inclusive() {
+        let mut rng = Rng::new(7);
+        let mut seen = [false; 5];
+
+        for _ in 0..1_000 {
+            let n = rng.range(-2, 2);
+            assert!((-2..=2).contains(&n));
+            seen[(n + 2) as usize] = true;
+        }
+
+        assert!(seen.iter().all(|s| *s));
+    }
+
+    #[test]
+    fn shuffle_keeps_items() {
+        let mut rng = Rng::new(3);
+        let mut items = (0..50).collect::<Vec<_>>();
+
+        rng.shuffle(&mut items);
+        items.sort();
+
+        assert_eq!(items, (0..50).collect::<Vec<_>>());
+    }
+}
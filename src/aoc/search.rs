@@ -0,0 +1,419 @@
+//! Searches over spaces of states that are only known through the moves
+//! out of each of them, for puzzles where building the whole graph first
+//! would be too big or too slow.
+//!
+//! Every search takes a [`Visited`] policy deciding which states are worth
+//! expanding again, and returns [`Stats`] along with what it found. Move
+//! costs whose sum overflows are ignored, like in [`Graph`].
+//!
+//! [`Graph`]: crate::aoc::graph::Graph
+
+use std::cmp::Reverse;
+use std::collections::hash_map::{Entry, HashMap};
+use std::collections::{BinaryHeap, VecDeque};
+use std::hash::Hash;
+
+use crate::aoc::graph::Weight;
+
+pub trait SearchState: Sized {
+    type Cost: Weight;
+
+    /// The states one move away, with the cost of the move.
+    fn successors(&self) -> Vec<(Self, Self::Cost)>;
+
+    fn is_goal(&self) -> bool;
+
+    /// A lower bound of the cost left to reach a goal. The default of zero
+    /// is always right when costs are not negative, a closer bound makes
+    /// A*, IDA* and branch and bound faster.
+    fn heuristic(&self) -> Self::Cost {
+        Self::Cost::default()
+    }
+}
+
+/// Decides whether a state reached at some cost should be expanded.
+pub trait Visited<S: SearchState> {
+    fn visit(&mut self, state: &S, cost: S::Cost) -> bool;
+}
+
+/// Expands every state every time it is reached, for state spaces without
+/// cycles or where the state already includes everything that matters.
+pub struct Unvisited;
+
+impl<S: SearchState> Visited<S> for Unvisited {
+    fn visit(&mut self, _state: &S, _cost: S::Cost) -> bool {
+        true
+    }
+}
+
+/// Expands a state again only when it is reached more cheaply than before.
+pub struct SeenStates<S: SearchState> {
+    best: HashMap<S, S::Cost>,
+}
+
+impl<S: SearchState> Default for SeenStates<S> {
+    fn default() -> Self {
+        SeenStates { best: HashMap::new() }
+    }
+}
+
+impl<S: SearchState> SeenStates<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S: SearchState + Hash + Eq + Clone> Visited<S> for SeenStates<S> {
+    fn visit(&mut self, state: &S, cost: S::Cost) -> bool {
+        match self.best.get_mut(state) {
+            Some(best) if *best <= cost => false,
+            Some(best) => {
+                *best = cost;
+                true
+            }
+            None => {
+                self.best.insert(state.clone(), cost);
+                true
+            }
+        }
+    }
+}
+
+/// Like [`SeenStates`], for states that are the same as far as the search
+/// is concerned when they have the same key.
+pub struct SeenKeys<K, C, F> {
+    key: F,
+    best: HashMap<K, C>,
+}
+
+impl<K, C, F> SeenKeys<K, C, F> {
+    pub fn new(key: F) -> Self {
+        SeenKeys { key, best: HashMap::new() }
+    }
+}
+
+impl<S, K, F> Visited<S> for SeenKeys<K, S::Cost, F>
+where
+    S: SearchState,
+    K: Hash + Eq,
+    F: Fn(&S) -> K,
+{
+    fn visit(&mut self, state: &S, cost: S::Cost) -> bool {
+        match self.best.entry((self.key)(state)) {
+            Entry::Occupied(best) if *best.get() <= cost => false,
+            Entry::Occupied(mut best) => {
+                best.insert(cost);
+                true
+            }
+            Entry::Vacant(best) => {
+                best.insert(cost);
+                true
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Stats {
+    /// States whose successors were generated.
+    pub expanded: usize,
+    /// The most states waiting to be expanded at the same time.
+    pub max_frontier: usize,
+}
+
+impl Stats {
+    fn expand(&mut self, frontier: usize) {
+        self.expanded += 1;
+        self.max_frontier = self.max_frontier.max(frontier);
+    }
+}
+
+/// A goal that was reached and the cost of getting there.
+pub type Found<S> = Option<(S, <S as SearchState>::Cost)>;
+
+// The successors of `state` reached at `cost`, that the policy lets through.
+fn next<S: SearchState>(state: &S, cost: S::Cost, visited: &mut impl Visited<S>) -> Vec<(S, S::Cost)> {
+    state
+        .successors()
+        .into_iter()
+        .filter_map(move |(next, step)| Some((next, cost.checked_add(step)?)))
+        .filter(|(next, cost)| visited.visit(next, *cost))
+        .collect()
+}
+
+/// The goal with the fewest moves.
+pub fn bfs<S: SearchState>(start: S, visited: &mut impl Visited<S>) -> (Found<S>, Stats) {
+    let mut stats = Stats::default();
+    visited.visit(&start, S::Cost::default());
+
+    let mut pending = VecDeque::from([(start, S::Cost::default())]);
+    while let Some((state, cost)) = pending.pop_front() {
+        if state.is_goal() {
+            return (Some((state, cost)), stats);
+        }
+
+        stats.expand(pending.len() + 1);
+        pending.extend(next(&state, cost, visited));
+    }
+
+    (None, stats)
+}
+
+/// The first goal found going as deep as possible first.
+pub fn dfs<S: SearchState>(start: S, visited: &mut impl Visited<S>) -> (Found<S>, Stats) {
+    let mut stats = Stats::default();
+    visited.visit(&start, S::Cost::default());
+
+    let mut pending = vec![(start, S::Cost::default())];
+    while let Some((state, cost)) = pending.pop() {
+        if state.is_goal() {
+            return (Some((state, cost)), stats);
+        }
+
+        stats.expand(pending.len() + 1);
+        pending.extend(next(&state, cost, visited));
+    }
+
+    (None, stats)
+}
+
+/// The cheapest goal. Costs must not be negative.
+pub fn dijkstra<S: SearchState>(start: S, visited: &mut impl Visited<S>) -> (Found<S>, Stats) {
+    best_first(start, visited, |_| S::Cost::default())
+}
+
+/// The cheapest goal, expanding first the states that look closest to one
+/// according to [`SearchState::heuristic`].
+pub fn astar<S: SearchState>(start: S, visited: &mut impl Visited<S>) -> (Found<S>, Stats) {
+    best_first(start, visited, S::heuristic)
+}
+
+fn best_first<S: SearchState>(
+    start: S,
+    visited: &mut impl Visited<S>,
+    heuristic: impl Fn(&S) -> S::Cost,
+) -> (Found<S>, Stats) {
+    let mut stats = Stats::default();
+    visited.visit(&start, S::Cost::default());
+
+    // states are kept aside so that they do not need to be ordered, the
+    // heap only holds their estimate and index
+    let mut pending = BinaryHeap::from([Reverse((heuristic(&start), 0))]);
+    let mut states = vec![Some((start, S::Cost::default()))];
+
+    while let Some(Reverse((_, index))) = pending.pop() {
+        let Some((state, cost)) = states[index].take() else { continue };
+        if state.is_goal() {
+            return (Some((state, cost)), stats);
+        }
+
+        stats.expand(pending.len() + 1);
+        for (next, cost) in next(&state, cost, visited) {
+            let Some(estimate) = cost.checked_add(heuristic(&next)) else { continue };
+            pending.push(Reverse((estimate, states.len())));
+            states.push(Some((next, cost)));
+        }
+    }
+
+    (None, stats)
+}
+
+/// The cheapest goal, found by depth first searches that give up on states
+/// whose estimated total cost is above a bound, raising the bound until a
+/// goal is found. It needs far less memory than A*, but nothing stops it
+/// from going around cycles, so moves must have a positive cost.
+pub fn ida_star<S: SearchState>(start: S) -> (Found<S>, Stats) {
+    let mut stats = Stats::default();
+    if start.is_goal() {
+        return (Some((start, S::Cost::default())), stats);
+    }
+
+    let mut bound = start.heuristic();
+    loop {
+        // the smallest estimate above the bound, for the next round
+        let mut above: Option<S::Cost> = None;
+
+        stats.expand(1);
+        let mut pending = next(&start, S::Cost::default(), &mut Unvisited);
+
+        while let Some((state, cost)) = pending.pop() {
+            let Some(estimate) = cost.checked_add(state.heuristic()) else { continue };
+            if estimate > bound {
+                above = Some(above.map_or(estimate, |a| a.min(estimate)));
+                continue;
+            }
+            if state.is_goal() {
+                return (Some((state, cost)), stats);
+            }
+
+            stats.expand(pending.len() + 1);
+            pending.extend(next(&state, cost, &mut Unvisited));
+        }
+
+        match above {
+            Some(next_bound) => bound = next_bound,
+            None => return (None, stats),
+        }
+    }
+}
+
+/// Goes through the states one layer of moves at a time, only keeping the
+/// `width` most promising states of each layer. Much faster than a full
+/// search on big state spaces, but the goal it finds, the cheapest of the
+/// first layer with one, may not be the cheapest overall.
+pub fn beam<S: SearchState>(start: S, width: usize, visited: &mut impl Visited<S>) -> (Found<S>, Stats) {
+    let mut stats = Stats::default();
+    visited.visit(&start, S::Cost::default());
+
+    let mut layer = vec![(start, S::Cost::default())];
+    while !layer.is_empty() {
+        let goal = layer
+            .iter()
+            .enumerate()
+            .filter(|(_, (state, _))| state.is_goal())
+            .min_by_key(|(_, (_, cost))| *cost)
+            .map(|(i, _)| i);
+        if let Some(i) = goal {
+            return (Some(layer.swap_remove(i)), stats);
+        }
+
+        let mut next_layer = vec![];
+        for (state, cost) in &layer {
+            stats.expand(layer.len());
+            next_layer.extend(next(state, *cost, visited).into_iter().filter_map(|(next, cost)| {
+                let estimate = cost.checked_add(next.heuristic())?;
+                Some((estimate, next, cost))
+            }));
+        }
+
+        next_layer.sort_by_key(|(estimate, _, _)| *estimate);
+        next_layer.truncate(width);
+        layer = next_layer.into_iter().map(|(_, state, cost)| (state, cost)).collect();
+    }
+
+    (None, stats)
+}
+
+/// The cheapest goal, going through every state depth first but skipping
+/// those that cannot lead to something cheaper than the best goal so far.
+pub fn branch_and_bound<S: SearchState>(start: S, visited: &mut impl Visited<S>) -> (Found<S>, Stats) {
+    let mut stats = Stats::default();
+    visited.visit(&start, S::Cost::default());
+
+    let mut best: Found<S> = None;
+    let mut pending = vec![(start, S::Cost::default())];
+    while let Some((state, cost)) = pending.pop() {
+        let hopeless = match (&best, cost.checked_add(state.heuristic())) {
+            (_, None) => true,
+            (Some((_, best)), Some(estimate)) => estimate >= *best,
+            (None, Some(_)) => false,
+        };
+        if hopeless {
+            continue;
+        }
+
+        if state.is_goal() {
+            best = Some((state, cost));
+            continue;
+        }
+
+        stats.expand(pending.len() + 1);
+        pending.extend(next(&state, cost, visited));
+    }
+
+    (best, stats)
+}
+
+/// Goes through every state and returns the goal whose cost wins when
+/// compared with `pick`, like `u32::max` to find the most expensive one.
+/// Goals are not expanded further.
+pub fn exhaustive<S: SearchState>(
+    start: S,
+    visited: &mut impl Visited<S>,
+    pick: impl Fn(S::Cost, S::Cost) -> S::Cost,
+) -> (Found<S>, Stats) {
+    let mut stats = Stats::default();
+    visited.visit(&start, S::Cost::default());
+
+    let mut best: Found<S> = None;
+    let mut pending = vec![(start, S::Cost::default())];
+    while let Some((state, cost)) = pending.pop() {
+        if state.is_goal() {
+            if best.as_ref().is_none_or(|(_, b)| pick(*b, cost) != *b) {
+                best = Some((state, cost));
+            }
+            continue;
+        }
+
+        stats.expand(pending.len() + 1);
+        pending.extend(next(&state, cost, visited));
+    }
+
+    (best, stats)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A number that can be doubled for 1 or have 1 added for 3, trying to
+    // reach a target.
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+    struct Number {
+        value: u32,
+        target: u32,
+    }
+
+    impl SearchState for Number {
+        type Cost = u32;
+
+        fn successors(&self) -> Vec<(Self, u32)> {
+            [(self.value * 2, 1), (self.value + 1, 3)]
+                .into_iter()
+                .filter(|&(value, _)| value <= self.target)
+                .map(|(value, cost)| (Number { value, target: self.target }, cost))
+                .collect()
+        }
+
+        fn is_goal(&self) -> bool {
+            self.value == self.target
+        }
+    }
+
+    fn start(target: u32) -> Number {
+        Number { value: 1, target }
+    }
+
+    #[test]
+    fn cheapest_paths_agree() {
+        // 1 -> 2 -> 3 -> 6 -> 12 -> 24 -> 25, for 1 + 3 + 1 + 1 + 1 + 3
+        let cost = |(found, _): (Found<Number>, Stats)| found.map(|(_, cost)| cost);
+
+        assert_eq!(cost(dijkstra(start(25), &mut SeenStates::new())), Some(10));
+        assert_eq!(cost(astar(start(25), &mut SeenStates::new())), Some(10));
+        assert_eq!(cost(ida_star(start(25))), Some(10));
+        assert_eq!(cost(branch_and_bound(start(25), &mut Unvisited)), Some(10));
+        assert_eq!(cost(exhaustive(start(25), &mut Unvisited, u32::min)), Some(10));
+        assert_eq!(cost(beam(start(25), 100, &mut SeenStates::new())), cost(bfs(start(25), &mut SeenStates::new())));
+    }
+
+    #[test]
+    fn visited_policies_cut_the_search() {
+        let (found, all) = dfs(start(40), &mut Unvisited);
+        assert!(found.is_some());
+
+        let (_, seen) = dijkstra(start(40), &mut SeenKeys::new(|n: &Number| n.value));
+        let (_, unseen) = dijkstra(start(40), &mut Unvisited);
+        assert!(seen.expanded < unseen.expanded);
+        assert!(all.max_frontier > 0);
+    }
+
+    #[test]
+    fn unreachable_goals() {
+        let unreachable = Number { value: 5, target: 3 };
+
+        assert!(bfs(unreachable.clone(), &mut Unvisited).0.is_none());
+        assert!(ida_star(unreachable.clone()).0.is_none());
+        assert!(exhaustive(unreachable, &mut Unvisited, u32::max).0.is_none());
+    }
+}
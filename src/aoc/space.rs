@@ -0,0 +1,537 @@
+//! Points and grids with any number of dimensions, for the puzzles with 3D
+//! voxels or 4D cellular automata.
+//!
+//! With two dimensions they match the plane helpers: [`PointN<2>`] has the
+//! same neighbors as a [`Point`] or a [`Grid`] position, all in reading
+//! order, and slices come out as a [`SparseGrid`] or a [`Grid`] to render
+//! them.
+
+use std::collections::hash_map::{self, HashMap};
+use std::fmt;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+
+use crate::aoc::geom::{Point, SparseGrid};
+use crate::aoc::grid::Grid;
+
+/// A position in a space of `D` dimensions, the first coordinate being the
+/// one that changes fastest when going through a box of points.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct PointN<const D: usize>(pub [i64; D]);
+
+pub type Point3 = PointN<3>;
+pub type Point4 = PointN<4>;
+
+impl<const D: usize> Default for PointN<D> {
+    fn default() -> Self {
+        Self::ORIGIN
+    }
+}
+
+impl<const D: usize> PointN<D> {
+    pub const ORIGIN: PointN<D> = PointN([0; D]);
+
+    pub const fn new(coordinates: [i64; D]) -> Self {
+        PointN(coordinates)
+    }
+
+    pub fn manhattan(self, other: PointN<D>) -> u64 {
+        self.0.iter().zip(other.0).map(|(a, b)| a.abs_diff(b)).sum()
+    }
+
+    /// The `3^D - 1` points around this one, diagonals included, in the
+    /// order [`PointN::between`] gives them, like [`Point::neighbors8`].
+    pub fn neighbors(self) -> impl Iterator<Item = PointN<D>> {
+        PointN::between(PointN([-1; D]), PointN([1; D]))
+            .filter(|&delta| delta != PointN::ORIGIN)
+            .map(move |delta| self + delta)
+    }
+
+    /// The `2 * D` points one step away along a single axis, in the order
+    /// [`PointN::between`] gives them, like [`Point::neighbors4`].
+    pub fn orthogonal_neighbors(self) -> impl Iterator<Item = PointN<D>> {
+        let before = (0..D).rev().map(|axis| (axis, -1));
+        let after = (0..D).map(|axis| (axis, 1));
+
+        before.chain(after).map(move |(axis, step)| {
+            let mut neighbor = self;
+            neighbor.0[axis] += step;
+            neighbor
+        })
+    }
+
+    /// Every point of the box with these corners, both included, with the
+    /// first coordinate changing fastest. Nothing when `min` is past `max`
+    /// on some axis.
+    pub fn between(min: PointN<D>, max: PointN<D>) -> impl Iterator<Item = PointN<D>> {
+        let mut next = (0..D).all(|axis| min.0[axis] <= max.0[axis]).then_some(min);
+
+        std::iter::from_fn(move || {
+            let current = next?;
+
+            next = None;
+            let mut point = current;
+            for axis in 0..D {
+                if point.0[axis] < max.0[axis] {
+                    point.0[axis] += 1;
+                    next = Some(point);
+                    break;
+                }
+                point.0[axis] = min.0[axis];
+            }
+
+            Some(current)
+        })
+    }
+
+    /// The smallest and biggest corners of the smallest box holding every
+    /// point.
+    pub fn bounds(points: impl IntoIterator<Item = PointN<D>>) -> Option<(PointN<D>, PointN<D>)> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold((first, first), |(min, max), p| {
+            (
+                PointN(std::array::from_fn(|axis| min.0[axis].min(p.0[axis]))),
+                PointN(std::array::from_fn(|axis| max.0[axis].max(p.0[axis]))),
+            )
+        }))
+    }
+}
+
+impl<const D: usize> Add for PointN<D> {
+    type Output = PointN<D>;
+
+    fn add(self, other: PointN<D>) -> PointN<D> {
+        PointN(std::array::from_fn(|axis| self.0[axis] + other.0[axis]))
+    }
+}
+
+impl<const D: usize> AddAssign for PointN<D> {
+    fn add_assign(&mut self, other: PointN<D>) {
+        *self = *self + other;
+    }
+}
+
+impl<const D: usize> Sub for PointN<D> {
+    type Output = PointN<D>;
+
+    fn sub(self, other: PointN<D>) -> PointN<D> {
+        PointN(std::array::from_fn(|axis| self.0[axis] - other.0[axis]))
+    }
+}
+
+impl<const D: usize> SubAssign for PointN<D> {
+    fn sub_assign(&mut self, other: PointN<D>) {
+        *self = *self - other;
+    }
+}
+
+impl<const D: usize> Mul<i64> for PointN<D> {
+    type Output = PointN<D>;
+
+    fn mul(self, factor: i64) -> PointN<D> {
+        PointN(self.0.map(|c| c * factor))
+    }
+}
+
+impl<const D: usize> Neg for PointN<D> {
+    type Output = PointN<D>;
+
+    fn neg(self) -> PointN<D> {
+        PointN(self.0.map(|c| -c))
+    }
+}
+
+impl<const D: usize> Index<usize> for PointN<D> {
+    type Output = i64;
+
+    fn index(&self, axis: usize) -> &i64 {
+        &self.0[axis]
+    }
+}
+
+impl<const D: usize> IndexMut<usize> for PointN<D> {
+    fn index_mut(&mut self, axis: usize) -> &mut i64 {
+        &mut self.0[axis]
+    }
+}
+
+impl<const D: usize> fmt::Display for PointN<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(")?;
+        for (axis, c) in self.0.iter().enumerate() {
+            if axis > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", c)?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+impl From<Point> for PointN<2> {
+    fn from(point: Point) -> Self {
+        PointN([point.x, point.y])
+    }
+}
+
+impl From<PointN<2>> for Point {
+    fn from(point: PointN<2>) -> Self {
+        Point::new(point.0[0], point.0[1])
+    }
+}
+
+// The point of a slice along `axes` at `point`, if `point` is in the plane
+// going through `at`.
+fn in_slice<const D: usize>(point: PointN<D>, axes: [usize; 2], at: PointN<D>) -> Option<Point> {
+    let in_plane = (0..D).all(|axis| axes.contains(&axis) || point.0[axis] == at.0[axis]);
+    in_plane.then(|| Point::new(point.0[axes[0]], point.0[axes[1]]))
+}
+
+/// Cells in an unbounded space, only storing the ones that were set. The
+/// same as [`SparseGrid`], in any number of dimensions.
+#[derive(Clone, Debug)]
+pub struct SparseGridN<const D: usize, T> {
+    cells: HashMap<PointN<D>, T>,
+}
+
+impl<const D: usize, T> Default for SparseGridN<D, T> {
+    fn default() -> Self {
+        SparseGridN { cells: HashMap::new() }
+    }
+}
+
+impl<const D: usize, T> SparseGridN<D, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, point: PointN<D>, value: T) -> Option<T> {
+        self.cells.insert(point, value)
+    }
+
+    pub fn remove(&mut self, point: PointN<D>) -> Option<T> {
+        self.cells.remove(&point)
+    }
+
+    pub fn get(&self, point: PointN<D>) -> Option<&T> {
+        self.cells.get(&point)
+    }
+
+    pub fn get_mut(&mut self, point: PointN<D>) -> Option<&mut T> {
+        self.cells.get_mut(&point)
+    }
+
+    pub fn entry(&mut self, point: PointN<D>) -> hash_map::Entry<'_, PointN<D>, T> {
+        self.cells.entry(point)
+    }
+
+    pub fn contains(&self, point: PointN<D>) -> bool {
+        self.cells.contains_key(&point)
+    }
+
+    /// The number of cells that were set.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (PointN<D>, &T)> {
+        self.cells.iter().map(|(&p, v)| (p, v))
+    }
+
+    /// The smallest and biggest corners of the smallest box holding every
+    /// cell that was set.
+    pub fn bounds(&self) -> Option<(PointN<D>, PointN<D>)> {
+        PointN::bounds(self.cells.keys().copied())
+    }
+
+    /// The plane through `at` along two axes, with `axes[0]` as `x` and
+    /// `axes[1]` as `y`, so that it can be rendered.
+    pub fn slice(&self, axes: [usize; 2], at: PointN<D>) -> SparseGrid<T>
+    where
+        T: Clone,
+    {
+        let mut slice = SparseGrid::new();
+        for (point, value) in self.iter() {
+            if let Some(point) = in_slice(point, axes, at) {
+                slice.insert(point, value.clone());
+            }
+        }
+
+        slice
+    }
+}
+
+impl<const D: usize, T> FromIterator<(PointN<D>, T)> for SparseGridN<D, T> {
+    fn from_iter<I: IntoIterator<Item = (PointN<D>, T)>>(cells: I) -> Self {
+        SparseGridN { cells: cells.into_iter().collect() }
+    }
+}
+
+impl<T: Clone> From<&SparseGrid<T>> for SparseGridN<2, T> {
+    fn from(grid: &SparseGrid<T>) -> Self {
+        grid.iter().map(|(p, v)| (p.into(), v.clone())).collect()
+    }
+}
+
+/// A dense box of cells between two corners, both included. The same as
+/// [`Grid`] in any number of dimensions, except that coordinates can be
+/// negative. Indexing panics outside the box, `get` returns `None` instead.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GridN<const D: usize, T> {
+    min: PointN<D>,
+    max: PointN<D>,
+    cells: Vec<T>,
+}
+
+impl<const D: usize, T: Clone> GridN<D, T> {
+    pub fn new(min: PointN<D>, max: PointN<D>, value: T) -> Self {
+        GridN::from_fn(min, max, |_| value.clone())
+    }
+}
+
+impl<const D: usize, T> GridN<D, T> {
+    pub fn from_fn(min: PointN<D>, max: PointN<D>, cell: impl FnMut(PointN<D>) -> T) -> Self {
+        GridN { min, max, cells: PointN::between(min, max).map(cell).collect() }
+    }
+
+    /// The smallest and biggest corners.
+    pub fn bounds(&self) -> (PointN<D>, PointN<D>) {
+        (self.min, self.max)
+    }
+
+    /// The number of cells.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    // Where a point is in `cells`, the first coordinate changing fastest.
+    fn offset(&self, point: PointN<D>) -> Option<usize> {
+        let mut offset = 0;
+        let mut stride = 1;
+        for axis in 0..D {
+            let (min, max) = (self.min.0[axis], self.max.0[axis]);
+            if !(min..=max).contains(&point.0[axis]) {
+                return None;
+            }
+
+            offset += (point.0[axis] - min) as usize * stride;
+            stride *= (max - min + 1) as usize;
+        }
+
+        Some(offset)
+    }
+
+    pub fn contains(&self, point: PointN<D>) -> bool {
+        self.offset(point).is_some()
+    }
+
+    pub fn get(&self, point: PointN<D>) -> Option<&T> {
+        self.offset(point).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, point: PointN<D>) -> Option<&mut T> {
+        self.offset(point).map(|i| &mut self.cells[i])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (PointN<D>, &T)> {
+        PointN::between(self.min, self.max).zip(&self.cells)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    /// The [`PointN::neighbors`] of a point that are inside the box.
+    pub fn neighbors(&self, point: PointN<D>) -> impl Iterator<Item = PointN<D>> + '_ {
+        point.neighbors().filter(|&p| self.contains(p))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> GridN<D, U> {
+        GridN { min: self.min, max: self.max, cells: self.cells.iter().map(f).collect() }
+    }
+
+    /// The plane through `at` along two axes, with `axes[0]` as `x` and
+    /// `axes[1]` as `y`. The slice starts at the smallest corner, so its
+    /// `(0, 0)` is where both axes are at their minimum.
+    ///
+    /// `None` when the axes are the same or not below `D`, or when `at` is
+    /// outside the box on one of the other axes.
+    pub fn slice(&self, axes: [usize; 2], at: PointN<D>) -> Option<Grid<T>>
+    where
+        T: Clone,
+    {
+        let [x, y] = axes;
+        if x == y || x >= D || y >= D {
+            return None;
+        }
+
+        let mut corner = at;
+        corner.0[x] = self.min.0[x];
+        corner.0[y] = self.min.0[y];
+        if !self.contains(corner) {
+            return None;
+        }
+
+        let width = (self.max.0[x] - self.min.0[x] + 1) as usize;
+        let height = (self.max.0[y] - self.min.0[y] + 1) as usize;
+
+        Some(Grid::from_fn(width, height, |dx, dy| {
+            let mut point = corner;
+            point.0[x] += dx as i64;
+            point.0[y] += dy as i64;
+            self[point].clone()
+        }))
+    }
+}
+
+impl<const D: usize, T> Index<PointN<D>> for GridN<D, T> {
+    type Output = T;
+
+    fn index(&self, point: PointN<D>) -> &T {
+        match self.offset(point) {
+            Some(i) => &self.cells[i],
+            None => panic!("{} is outside a grid from {} to {}", point, self.min, self.max),
+        }
+    }
+}
+
+impl<const D: usize, T> IndexMut<PointN<D>> for GridN<D, T> {
+    fn index_mut(&mut self, point: PointN<D>) -> &mut T {
+        match self.offset(point) {
+            Some(i) => &mut self.cells[i],
+            None => panic!("{} is outside a grid from {} to {}", point, self.min, self.max),
+        }
+    }
+}
+
+impl<T: Clone> From<&Grid<T>> for GridN<2, T> {
+    fn from(grid: &Grid<T>) -> Self {
+        let max = PointN([grid.width() as i64 - 1, grid.height() as i64 - 1]);
+        GridN::from_fn(PointN::ORIGIN, max, |p| grid[(p.0[0] as usize, p.0[1] as usize)].clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // One cycle of Conway's game of life, in any number of dimensions.
+    fn life<const D: usize>(active: &SparseGridN<D, ()>) -> SparseGridN<D, ()> {
+        let mut counts = SparseGridN::<D, usize>::new();
+        for (point, _) in active.iter() {
+            for neighbor in point.neighbors() {
+                *counts.entry(neighbor).or_default() += 1;
+            }
+        }
+
+        counts
+            .iter()
+            .filter(|&(p, &n)| n == 3 || (n == 2 && active.contains(p)))
+            .map(|(p, _)| (p, ()))
+            .collect()
+    }
+
+    fn glider<const D: usize>() -> SparseGridN<D, ()> {
+        let grid = Grid::parse(".#.\n..#\n###", |c| Some(c == '#'), "a cell").unwrap();
+        grid.iter()
+            .filter(|(_, &active)| active)
+            .map(|((x, y), _)| {
+                let mut point = PointN::ORIGIN;
+                point.0[0] = x as i64;
+                point.0[1] = y as i64;
+                (point, ())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn neighbors_in_any_dimension() {
+        assert_eq!(Point3::ORIGIN.neighbors().count(), 26);
+        assert_eq!(Point4::ORIGIN.neighbors().count(), 80);
+        assert_eq!(Point4::ORIGIN.orthogonal_neighbors().count(), 8);
+        assert_eq!(PointN::new([1, 2, 3]).manhattan(-PointN::new([1, 2, 3])), 12);
+    }
+
+    #[test]
+    fn plane_neighbors_agree() {
+        let grid = Grid::new(3, 3, ());
+        let center = PointN::new([1, 1]);
+        let from_grid = |positions: Vec<(usize, usize)>| {
+            positions.into_iter().map(|(x, y)| PointN::new([x as i64, y as i64])).collect::<Vec<_>>()
+        };
+
+        let orthogonal = center.orthogonal_neighbors().collect::<Vec<_>>();
+        assert_eq!(Point::from(center).neighbors4().map(PointN::from).collect::<Vec<_>>(), orthogonal);
+        assert_eq!(from_grid(grid.neighbors4((1, 1)).collect()), orthogonal);
+
+        let all = center.neighbors().collect::<Vec<_>>();
+        assert_eq!(Point::from(center).neighbors8().map(PointN::from).collect::<Vec<_>>(), all);
+        assert_eq!(from_grid(grid.neighbors8((1, 1)).collect()), all);
+
+        let around = PointN::between(center - PointN::new([1, 1]), center + PointN::new([1, 1]));
+        assert_eq!(all, around.filter(|&p| p != center).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn boxes_of_points() {
+        let points = PointN::between(PointN::new([0, 0, 0]), PointN::new([1, 2, 0])).collect::<Vec<_>>();
+        assert_eq!(points.len(), 6);
+        assert_eq!((points[1], points[2]), (PointN::new([1, 0, 0]), PointN::new([0, 1, 0])));
+        assert_eq!(PointN::between(PointN::new([1]), PointN::new([0])).count(), 0);
+
+        let bounds = PointN::bounds([PointN::new([1, -2]), PointN::new([-1, 5])]);
+        assert_eq!(bounds, Some((PointN::new([-1, -2]), PointN::new([1, 5]))));
+    }
+
+    #[test]
+    fn conway_cubes() {
+        let (mut cubes, mut hypercubes) = (glider::<3>(), glider::<4>());
+        for _ in 0..6 {
+            cubes = life(&cubes);
+            hypercubes = life(&hypercubes);
+        }
+
+        assert_eq!((cubes.len(), hypercubes.len()), (112, 848));
+    }
+
+    #[test]
+    fn slices_for_display() {
+        let cubes = life(&glider::<3>());
+        let (min, max) = cubes.bounds().unwrap();
+        assert_eq!((min, max), (PointN::new([0, 1, -1]), PointN::new([2, 3, 1])));
+
+        let layer = cubes.slice([0, 1], PointN::new([0, 0, -1]));
+        assert_eq!(layer.render(|c| if c.is_some() { '#' } else { '.' }), "#..\n..#\n.#.");
+
+        let dense = GridN::from_fn(min, max, |p| cubes.contains(p));
+        assert_eq!(dense.len(), 27);
+        assert_eq!(dense.neighbors(min).count(), 7);
+        assert_eq!(dense.slice([0, 1], PointN::new([0, 0, 0])).unwrap().render(|&c| if c { '#' } else { '.' }), "#.#\n.##\n.#.");
+        assert_eq!(dense.slice([0, 1], PointN::new([0, 0, 2])), None);
+        assert_eq!(dense.slice([1, 1], min), None);
+        assert_eq!(dense.slice([0, 3], min), None);
+    }
+
+    #[test]
+    fn plane_grids_convert() {
+        let grid = Grid::from_fn(3, 2, |x, y| x + 10 * y);
+        let dense = GridN::from(&grid);
+        assert_eq!(dense[PointN::new([2, 1])], 12);
+        assert_eq!(dense.slice([0, 1], PointN::ORIGIN), Some(grid));
+        assert_eq!(dense.get(PointN::new([3, 0])), None);
+    }
+}
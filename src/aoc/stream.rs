@@ -0,0 +1,311 @@
+//! Solving from a reader instead of a string, for inputs too big to load
+//! into memory at once.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, ErrorKind};
+
+use crate::aoc::parse::Cursor;
+use crate::aoc::{Error, ParseError, Result};
+
+/// A solution that can go through its input in a single pass. It must give
+/// the same results and errors as the `DaySolution` of the same day.
+pub trait StreamingSolution<T> {
+    fn stream_part1(&self, input: &mut dyn BufRead) -> Result<T>;
+
+    fn stream_part2(&self, input: &mut dyn BufRead) -> Result<T>;
+}
+
+fn read_error(e: std::io::Error) -> Error {
+    Error::GenericError {
+        message: format!("could not read the input: {}", e),
+        source: Box::new(e),
+    }
+}
+
+/// Where a character was found, both starting at 1.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+// Longest start of a line kept to show next to errors. Errors further into
+// the line are reported without it.
+const EXCERPT_LEN: usize = 200;
+
+// Most whitespace kept back while waiting to see whether it is at the end
+// of the input. A longer run inside the input is cut short with an error,
+// so that memory stays bounded.
+const MAX_SPACES: usize = 1 << 16;
+
+/// The characters of a stream with their position, leaving out whitespace
+/// at the start and end of the input, like `str::trim` does.
+///
+/// A run of more than 65536 whitespace characters inside the input gives
+/// its first 65536 characters followed by an `InvalidInput` error.
+pub struct Chars<'a> {
+    input: &'a mut dyn BufRead,
+    bytes: Vec<u8>,
+    next: Position,
+    excerpt: String,
+    started: bool,
+    spaces: VecDeque<(char, Position)>,
+    // whitespace past `MAX_SPACES` that was read but not kept
+    skipped: usize,
+    following: Option<(char, Position)>,
+}
+
+impl<'a> Chars<'a> {
+    pub fn new(input: &'a mut dyn BufRead) -> Self {
+        Chars {
+            input,
+            bytes: vec![],
+            next: Position { line: 1, column: 1 },
+            excerpt: String::new(),
+            started: false,
+            spaces: VecDeque::new(),
+            skipped: 0,
+            following: None,
+        }
+    }
+
+    /// A parse error for the character at `position`, which must be the last
+    /// one returned.
+    pub fn error(&self, position: Position, expected: impl Into<String>) -> Error {
+        let excerpt = self.excerpt.chars().count() >= position.column && position.line == self.next.line;
+
+        Error::Parse(ParseError {
+            line: position.line,
+            column: position.column,
+            len: 1,
+            text: if excerpt { self.excerpt.clone() } else { String::new() },
+            expected: expected.into(),
+        })
+    }
+
+    // Decodes the next character straight from the reader's buffer. A
+    // character split across two fills of the buffer is put together in
+    // `bytes`.
+    fn read_char(&mut self) -> Option<Result<(char, Position)>> {
+        let c = loop {
+            let buffer = match self.input.fill_buf() {
+                Ok(buffer) => buffer,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(read_error(e))),
+            };
+
+            // the input ended in the middle of a character
+            if buffer.is_empty() {
+                let partial = !self.bytes.is_empty();
+                self.bytes.clear();
+                return partial.then_some(Err(Error::InvalidInput));
+            }
+
+            let width = match self.bytes.first().unwrap_or(&buffer[0]) {
+                0x00..=0x7f => 1,
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => {
+                    self.input.consume(1);
+                    return Some(Err(Error::InvalidInput));
+                }
+            };
+
+            if self.bytes.is_empty() && buffer.len() >= width {
+                let c = std::str::from_utf8(&buffer[..width]).ok().and_then(|s| s.chars().next());
+                self.input.consume(width);
+                break c;
+            }
+
+            let take = (width - self.bytes.len()).min(buffer.len());
+            self.bytes.extend_from_slice(&buffer[..take]);
+            self.input.consume(take);
+
+            if self.bytes.len() == width {
+                let c = std::str::from_utf8(&self.bytes).ok().and_then(|s| s.chars().next());
+                self.bytes.clear();
+                break c;
+            }
+        };
+
+        let c = match c {
+            Some(c) => c,
+            None => return Some(Err(Error::InvalidInput)),
+        };
+
+        let position = self.next;
+        if c == '\n' {
+            self.next = Position { line: position.line + 1, column: 1 };
+            self.excerpt.clear();
+        } else {
+            self.next.column += 1;
+            if position.column <= EXCERPT_LEN {
+                self.excerpt.push(c);
+            }
+        }
+
+        Some(Ok((c, position)))
+    }
+}
+
+impl<'a> Iterator for Chars<'a> {
+    type Item = Result<(char, Position)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(space) = self.spaces.pop_front() {
+            return Some(Ok(space));
+        }
+
+        if self.skipped > 0 {
+            self.skipped = 0;
+            return Some(Err(Error::InvalidInput));
+        }
+
+        if let Some(following) = self.following.take() {
+            return Some(Ok(following));
+        }
+
+        loop {
+            let (c, position) = match self.read_char()? {
+                Ok(next) => next,
+                Err(e) => return Some(Err(e)),
+            };
+
+            // whitespace is only returned once something follows it
+            if !c.is_whitespace() {
+                self.started = true;
+                self.following = Some((c, position));
+                return self.next();
+            }
+
+            if self.started && self.spaces.len() < MAX_SPACES {
+                self.spaces.push_back((c, position));
+            } else if self.started {
+                self.skipped += 1;
+            }
+        }
+    }
+}
+
+/// Reads every non-blank line of a stream as a whole record, like
+/// `parse::lines` does for a string. Records cannot borrow from the line.
+pub fn lines<'a, T>(
+    input: &'a mut dyn BufRead,
+    record: impl for<'l> Fn(&mut Cursor<'l>) -> Result<T> + 'a,
+) -> impl Iterator<Item = Result<T>> + 'a {
+    let mut line = String::new();
+    let mut number = 0;
+
+    std::iter::from_fn(move || loop {
+        line.clear();
+        number += 1;
+        match input.read_line(&mut line) {
+            Ok(0) => return None,
+            Ok(_) => (),
+            Err(e) => return Some(Err(read_error(e))),
+        }
+
+        let text = line.trim_end_matches(['\n', '\r']);
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        let mut cursor = Cursor::on_line(text, number);
+        return Some(record(&mut cursor).and_then(|value| cursor.end().map(|_| value)));
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn chars_are_trimmed() {
+        let mut input = "  \n(é) \n)\n\n".as_bytes();
+        let chars = Chars::new(&mut input)
+            .map(|c| c.unwrap())
+            .map(|(c, p)| (c, p.line, p.column))
+            .collect::<Vec<_>>();
+
+        assert_eq!(chars, vec![
+            ('(', 2, 1),
+            ('é', 2, 2),
+            (')', 2, 3),
+            (' ', 2, 4),
+            ('\n', 2, 5),
+            (')', 3, 1),
+        ]);
+    }
+
+    // Gives one byte per read, after being interrupted first.
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+        interrupted: bool,
+    }
+
+    impl std::io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.interrupted = !self.interrupted;
+            if self.interrupted {
+                return Err(ErrorKind::Interrupted.into());
+            }
+
+            let n = self.bytes.len().min(buf.len()).min(1);
+            buf[..n].copy_from_slice(&self.bytes[..n]);
+            self.bytes = &self.bytes[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn chars_split_across_reads() {
+        let reader = Trickle { bytes: "é€𝄞".as_bytes(), interrupted: false };
+        let mut input = std::io::BufReader::with_capacity(2, reader);
+        let chars = Chars::new(&mut input).map(|c| c.unwrap().0).collect::<String>();
+        assert_eq!(chars, "é€𝄞");
+
+        let mut input = &b"(\xe2\x82"[..];
+        let chars = Chars::new(&mut input).collect::<Vec<_>>();
+        assert!(matches!(chars[..], [Ok(('(', _)), Err(Error::InvalidInput)]));
+    }
+
+    #[test]
+    fn long_runs_of_whitespace() {
+        let trailing = format!("({}", "\n".repeat(MAX_SPACES + 10));
+        assert_eq!(Chars::new(&mut trailing.as_bytes()).count(), 1);
+
+        let inside = format!("({})", " ".repeat(MAX_SPACES + 10));
+        let chars = Chars::new(&mut inside.as_bytes()).collect::<Vec<_>>();
+        assert_eq!(chars.len(), 1 + MAX_SPACES + 2);
+        assert!(matches!(chars[1 + MAX_SPACES], Err(Error::InvalidInput)));
+        assert!(matches!(chars.last(), Some(Ok((')', _)))));
+
+        let fits = format!("({})", " ".repeat(MAX_SPACES));
+        assert!(Chars::new(&mut fits.as_bytes()).all(|c| c.is_ok()));
+    }
+
+    #[test]
+    fn error_on_current_line() {
+        let mut input = "()\n(x)".as_bytes();
+        let mut chars = Chars::new(&mut input);
+        let (_, position) = chars.find(|c| matches!(c, Ok(('x', _)))).unwrap().unwrap();
+
+        match chars.error(position, "`(` or `)`") {
+            Error::Parse(e) => assert_eq!(e.to_string(), "line 2, column 2: expected `(` or `)`\n    (x\n     ^"),
+            other => panic!("expected a parse error, got: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lines_keep_line_numbers() {
+        let mut input = "1\n\n2\nx\n".as_bytes();
+        let values = lines(&mut input, |c| c.integer::<u32>()).collect::<Vec<_>>();
+
+        assert_eq!(values.len(), 3);
+        match &values[2] {
+            Err(Error::Parse(e)) => assert_eq!((e.line, e.column), (4, 1)),
+            other => panic!("expected a parse error, got: {:?}", other),
+        }
+    }
+}
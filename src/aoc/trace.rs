@@ -0,0 +1,133 @@
+//! Structured events from inside solvers, to follow what a solution does
+//! without adding and removing prints.
+//!
+//! Events are only recorded with the `trace` feature. Without it
+//! [`event`] does nothing and the closure building the fields is never
+//! called, so solvers can leave their events in place.
+
+use std::fmt;
+
+/// Whether this build records events at all.
+pub const ENABLED: bool = cfg!(feature = "trace");
+
+pub type Fields = Vec<(&'static str, String)>;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Event {
+    pub kind: &'static str,
+    pub fields: Fields,
+}
+
+/// One line, the kind followed by `key=value` pairs.
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        for (key, value) in &self.fields {
+            write!(f, " {}={}", key, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "trace")]
+mod sink {
+    use super::{Event, Fields};
+    use std::cell::RefCell;
+
+    type Sink = Box<dyn FnMut(Event)>;
+
+    thread_local! {
+        static SINK: RefCell<Option<Sink>> = const { RefCell::new(None) };
+    }
+
+    // puts the previous sink back, even if the traced code panics
+    struct Restore(Option<Sink>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            SINK.with(|s| *s.borrow_mut() = previous);
+        }
+    }
+
+    pub fn with_sink<T>(sink: impl FnMut(Event) + 'static, f: impl FnOnce() -> T) -> T {
+        let previous = SINK.with(|s| s.borrow_mut().replace(Box::new(sink)));
+        let _restore = Restore(previous);
+
+        f()
+    }
+
+    pub fn event(kind: &'static str, fields: impl FnOnce() -> Fields) {
+        SINK.with(|s| {
+            // events sent by the sink itself are dropped
+            if let Ok(mut sink) = s.try_borrow_mut() {
+                if let Some(sink) = sink.as_mut() {
+                    sink(Event { kind, fields: fields() });
+                }
+            }
+        });
+    }
+}
+
+#[cfg(not(feature = "trace"))]
+mod sink {
+    use super::{Event, Fields};
+
+    #[inline(always)]
+    pub fn with_sink<T>(_sink: impl FnMut(Event) + 'static, f: impl FnOnce() -> T) -> T {
+        f()
+    }
+
+    #[inline(always)]
+    pub fn event(_kind: &'static str, _fields: impl FnOnce() -> Fields) {}
+}
+
+/// Runs `f`, passing every event it records on this thread to `sink`.
+pub fn with_sink<T>(sink: impl FnMut(Event) + 'static, f: impl FnOnce() -> T) -> T {
+    sink::with_sink(sink, f)
+}
+
+/// Records an event of the given kind. `fields` is only called when
+/// someone is listening.
+#[inline(always)]
+pub fn event(kind: &'static str, fields: impl FnOnce() -> Fields) {
+    sink::event(kind, fields)
+}
+
+/// Runs `f` and returns the events it recorded, for tests.
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<Event>) {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let events = Rc::new(RefCell::new(vec![]));
+    let sink = Rc::clone(&events);
+    let result = with_sink(move |e| sink.borrow_mut().push(e), f);
+
+    let events = events.take();
+    (result, events)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn events_need_the_feature() {
+        let ((), events) = capture(|| {
+            event("step", || vec![("n", 1.to_string())]);
+            event("step", || vec![("n", 2.to_string())]);
+        });
+
+        if ENABLED {
+            assert_eq!(events.iter().map(|e| e.to_string()).collect::<Vec<_>>(), vec!["step n=1", "step n=2"]);
+        } else {
+            assert!(events.is_empty());
+        }
+
+        // the sink is gone once `capture` returns
+        let (_, outside) = capture(|| ());
+        event("ignored", || panic!("fields built without a sink"));
+        assert!(outside.is_empty());
+    }
+}
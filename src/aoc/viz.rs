@@ -0,0 +1,300 @@
+//! Pictures of the state of a solver, written as image files or drawn in
+//! the terminal.
+//!
+//! A day implements [`Visualize`] and sends frames to a [`FrameSink`] as it
+//! goes, so long animations never have to be kept in memory.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::aoc::grid::Grid;
+use crate::aoc::{Error, Result};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb::new(0, 0, 0);
+    pub const WHITE: Rgb = Rgb::new(255, 255, 255);
+    pub const RED: Rgb = Rgb::new(220, 50, 47);
+    pub const GREEN: Rgb = Rgb::new(60, 180, 75);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Rgb { r, g, b }
+    }
+
+    pub const fn gray(level: u8) -> Self {
+        Rgb::new(level, level, level)
+    }
+
+    /// The color `t` of the way from `self` to `other`, with `t` between 0
+    /// and 1.
+    pub fn mix(self, other: Rgb, t: f64) -> Rgb {
+        let t = t.clamp(0.0, 1.0);
+        let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+
+        Rgb::new(channel(self.r, other.r), channel(self.g, other.g), channel(self.b, other.b))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Frame {
+    Gray(Grid<u8>),
+    Color(Grid<Rgb>),
+}
+
+impl Frame {
+    pub fn width(&self) -> usize {
+        match self {
+            Frame::Gray(image) => image.width(),
+            Frame::Color(image) => image.width(),
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        match self {
+            Frame::Gray(image) => image.height(),
+            Frame::Color(image) => image.height(),
+        }
+    }
+
+    pub fn pixel(&self, position: (usize, usize)) -> Rgb {
+        match self {
+            Frame::Gray(image) => Rgb::gray(image[position]),
+            Frame::Color(image) => image[position],
+        }
+    }
+}
+
+/// Where frames go.
+pub trait FrameSink {
+    fn frame(&mut self, frame: &Frame) -> Result<()>;
+}
+
+/// Keeps every frame, mostly for tests.
+impl FrameSink for Vec<Frame> {
+    fn frame(&mut self, frame: &Frame) -> Result<()> {
+        self.push(frame.clone());
+        Ok(())
+    }
+}
+
+pub trait Visualize {
+    /// Sends pictures of the state of the solution for `input` to `sink`,
+    /// in order.
+    fn visualize(&self, input: &str, sink: &mut dyn FrameSink) -> Result<()>;
+}
+
+fn write_error(e: io::Error) -> Error {
+    Error::GenericError {
+        message: format!("could not write the frame: {}", e),
+        source: Box::new(e),
+    }
+}
+
+/// Writes a binary PGM image.
+pub fn write_pgm(out: &mut dyn Write, image: &Grid<u8>) -> io::Result<()> {
+    write!(out, "P5\n{} {}\n255\n", image.width(), image.height())?;
+    for row in image.rows() {
+        out.write_all(row)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a binary PPM image.
+pub fn write_ppm(out: &mut dyn Write, image: &Grid<Rgb>) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", image.width(), image.height())?;
+    for row in image.rows() {
+        let bytes = row.iter().flat_map(|p| [p.r, p.g, p.b]).collect::<Vec<_>>();
+        out.write_all(&bytes)?;
+    }
+
+    Ok(())
+}
+
+/// Writes every frame to its own numbered file in a directory, as a PGM or
+/// PPM image depending on the frame. Most tools can turn the sequence into
+/// an animation, for example `ffmpeg -i frame-%05d.ppm out.gif`.
+pub struct FrameFiles {
+    dir: PathBuf,
+    count: usize,
+}
+
+impl FrameFiles {
+    /// The directory must already exist.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FrameFiles { dir: dir.into(), count: 0 }
+    }
+}
+
+impl FrameSink for FrameFiles {
+    fn frame(&mut self, frame: &Frame) -> Result<()> {
+        let extension = match frame {
+            Frame::Gray(_) => "pgm",
+            Frame::Color(_) => "ppm",
+        };
+        let path = self.dir.join(format!("frame-{:05}.{}", self.count, extension));
+        self.count += 1;
+
+        let mut out = BufWriter::new(File::create(path).map_err(write_error)?);
+        match frame {
+            Frame::Gray(image) => write_pgm(&mut out, image),
+            Frame::Color(image) => write_ppm(&mut out, image),
+        }
+        .and_then(|_| out.flush())
+        .map_err(write_error)
+    }
+}
+
+/// Draws frames with ANSI colors, two pixels per character, each frame over
+/// the previous one. Frames wider than `max_width` are scaled down.
+pub struct Terminal<W> {
+    out: W,
+    max_width: usize,
+    delay: Duration,
+    lines: usize,
+}
+
+impl<W: Write> Terminal<W> {
+    pub fn new(out: W, max_width: usize, delay: Duration) -> Self {
+        Terminal { out, max_width: max_width.max(1), delay, lines: 0 }
+    }
+
+    fn draw(&mut self, frame: &Frame) -> io::Result<()> {
+        let scale = frame.width().div_ceil(self.max_width).max(1);
+        let (width, height) = (frame.width() / scale, frame.height() / scale);
+
+        if self.lines > 0 {
+            write!(self.out, "\x1b[{}A", self.lines)?;
+        }
+
+        let mut text = String::new();
+        for y in (0..height).step_by(2) {
+            for x in 0..width {
+                let top = frame.pixel((x * scale, y * scale));
+                let bottom = if y + 1 < height {
+                    frame.pixel((x * scale, (y + 1) * scale))
+                } else {
+                    Rgb::BLACK
+                };
+                text.push_str(&format!(
+                    "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                    top.r, top.g, top.b, bottom.r, bottom.g, bottom.b));
+            }
+            text.push_str("\x1b[0m\n");
+        }
+
+        self.out.write_all(text.as_bytes())?;
+        self.out.flush()?;
+        self.lines = height.div_ceil(2);
+
+        Ok(())
+    }
+}
+
+impl<W: Write> FrameSink for Terminal<W> {
+    fn frame(&mut self, frame: &Frame) -> Result<()> {
+        self.draw(frame).map_err(write_error)?;
+        thread::sleep(self.delay);
+
+        Ok(())
+    }
+}
+
+/// Draws a straight line between two points, leaving out the parts outside
+/// the image.
+pub fn draw_line(image: &mut Grid<Rgb>, from: (i64, i64), to: (i64, i64), color: Rgb) {
+    // Bresenham's algorithm, which works for every direction
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (sx, sy) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let (mut x, mut y) = from;
+    let mut error = dx + dy;
+
+    loop {
+        set_pixel(image, (x, y), color);
+        if (x, y) == to {
+            return;
+        }
+
+        let e2 = 2 * error;
+        if e2 >= dy {
+            error += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
+
+/// Fills the disc of the given radius around `center`.
+pub fn fill_circle(image: &mut Grid<Rgb>, center: (i64, i64), radius: i64, color: Rgb) {
+    for y in -radius..=radius {
+        for x in -radius..=radius {
+            if x * x + y * y <= radius * radius {
+                set_pixel(image, (center.0 + x, center.1 + y), color);
+            }
+        }
+    }
+}
+
+fn set_pixel(image: &mut Grid<Rgb>, (x, y): (i64, i64), color: Rgb) {
+    if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
+        if let Some(pixel) = image.get_mut((x, y)) {
+            *pixel = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn image_files() {
+        let gray = Grid::from_fn(2, 2, |x, y| (x + 2 * y) as u8);
+        let mut pgm = vec![];
+        write_pgm(&mut pgm, &gray).unwrap();
+        assert_eq!(pgm, b"P5\n2 2\n255\n\x00\x01\x02\x03");
+
+        let color = Grid::new(1, 1, Rgb::new(1, 2, 3));
+        let mut ppm = vec![];
+        write_ppm(&mut ppm, &color).unwrap();
+        assert_eq!(ppm, b"P6\n1 1\n255\n\x01\x02\x03");
+    }
+
+    #[test]
+    fn terminal_redraws_in_place() {
+        let frame = Frame::Gray(Grid::from_fn(4, 3, |x, _| if x < 2 { 255 } else { 0 }));
+        let mut terminal = Terminal::new(vec![], 2, Duration::ZERO);
+        terminal.frame(&frame).unwrap();
+        terminal.frame(&frame).unwrap();
+
+        let text = String::from_utf8(terminal.out).unwrap();
+        let first = "\x1b[38;2;255;255;255m\x1b[48;2;0;0;0m\u{2580}\x1b[38;2;0;0;0m\x1b[48;2;0;0;0m\u{2580}\x1b[0m\n";
+        assert_eq!(text, format!("{}\x1b[1A{}", first, first));
+    }
+
+    #[test]
+    fn lines_and_circles() {
+        let mut image = Grid::new(5, 5, Rgb::BLACK);
+        draw_line(&mut image, (0, 0), (4, 2), Rgb::WHITE);
+        fill_circle(&mut image, (4, 4), 1, Rgb::RED);
+
+        let rendered = image.render(|&p| match p {
+            Rgb::WHITE => '#',
+            Rgb::RED => 'o',
+            _ => '.',
+        });
+        assert_eq!(rendered, "#....\n.##..\n...##\n....o\n...oo");
+    }
+}
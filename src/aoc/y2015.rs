@@ -1,6 +1,7 @@
 pub mod day01;
 pub mod day02;
 pub mod day03;
+#[cfg(feature = "y2015-day04")]
 pub mod day04;
 pub mod day05;
 pub mod day06;
@@ -9,5 +10,6 @@ pub mod day08;
 pub mod day09;
 pub mod day10;
 pub mod day11;
+#[cfg(feature = "y2015-day12")]
 pub mod day12;
 pub mod day13;
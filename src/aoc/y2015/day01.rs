@@ -1,5 +1,12 @@
-use crate::aoc::{DaySolution, Error, Result};
-
+use crate::aoc::debug::{Simulate, Simulation};
+use crate::aoc::generator::InputGenerator;
+use crate::aoc::rng::Rng;
+use crate::aoc::stream::{Chars, StreamingSolution};
+use crate::aoc::trace::Fields;
+use crate::aoc::{all_errors, DaySolution, Error, Result, Validate};
+use std::io::BufRead;
+
+#[derive(Clone)]
 enum Step {
     Up,
     Down,
@@ -9,54 +16,141 @@ pub struct Solution;
 
 impl Solution {
     fn parse_input(&self, input: &str) -> Result<Vec<Step>> {
-        let mut data = Vec::new();
-        for c in input.trim().chars() {
-            data.push(match c {
-                '(' => Step::Up,
-                ')' => Step::Down,
-                _ => return Err(Error::InvalidInput),
-            });
-        }
+        self.steps(input).collect()
+    }
 
-        Ok(data)
+    fn steps<'a>(&self, input: &'a str) -> impl Iterator<Item = Result<Step>> + 'a {
+        let trimmed = input.trim();
+
+        trimmed.char_indices().map(move |(i, c)| {
+            parse_step(c).ok_or_else(|| Error::parse(input, &trimmed[i..i + c.len_utf8()], "`(` or `)`"))
+        })
     }
 }
 
+fn parse_step(c: char) -> Option<Step> {
+    match c {
+        '(' => Some(Step::Up),
+        ')' => Some(Step::Down),
+        _ => None,
+    }
+}
+
+fn stream_steps<'a>(input: &'a mut dyn BufRead) -> impl Iterator<Item = Result<Step>> + 'a {
+    let mut chars = Chars::new(input);
+
+    std::iter::from_fn(move || {
+        let next = chars.next()?;
+        Some(next.and_then(|(c, position)| parse_step(c).ok_or_else(|| chars.error(position, "`(` or `)`"))))
+    })
+}
+
+impl Validate for Solution {
+    fn validate(&self, input: &str) -> Result<()> {
+        all_errors(self.steps(input))
+    }
+}
+
+// Both parts go through the steps lazily, so the string and streaming
+// solutions read the same amount of the input: part 2 stops at the
+// basement without looking at what follows.
 impl DaySolution<i32> for Solution {
     fn solve_part1(&self, input: &str) -> Result<i32> {
-        let data = self.parse_input(input)?;
-        Ok(data
-             .iter()
-             .map(|s| match s {
-                 Step::Up => 1,
-                 Step::Down => -1,
-             })
-             .sum::<i32>())
+        final_floor(self.steps(input))
     }
 
     fn solve_part2(&self, input: &str) -> Result<i32> {
-        let data = self.parse_input(input)?;
-
-        let mut floor = 0;
-        for (i, c) in data.iter().enumerate() {
-            floor += match c {
-                Step::Up => 1,
-                Step::Down => -1,
-            };
-
-            if floor == -1 {
-                return Ok((i+1).try_into().unwrap());
-            }
+        basement(self.steps(input))
+    }
+}
+
+impl StreamingSolution<i32> for Solution {
+    fn stream_part1(&self, input: &mut dyn BufRead) -> Result<i32> {
+        final_floor(stream_steps(input))
+    }
+
+    fn stream_part2(&self, input: &mut dyn BufRead) -> Result<i32> {
+        basement(stream_steps(input))
+    }
+}
+
+fn climb(floor: i32, step: &Step) -> Result<i32> {
+    match step {
+        Step::Up => floor.checked_add(1),
+        Step::Down => floor.checked_sub(1),
+    }
+    .ok_or(Error::InvalidInput)
+}
+
+fn final_floor(mut steps: impl Iterator<Item = Result<Step>>) -> Result<i32> {
+    steps.try_fold(0, |floor, step| climb(floor, &step?))
+}
+
+// The number of the first step that reaches floor -1.
+fn basement(steps: impl Iterator<Item = Result<Step>>) -> Result<i32> {
+    let mut floor = 0;
+    for (i, step) in steps.enumerate() {
+        floor = climb(floor, &step?)?;
+
+        if floor == -1 {
+            return (i+1).try_into().map_err(|_| Error::InvalidInput);
         }
+    }
+
+    Err(Error::ResultNotFound)
+}
+
+/// Santa following the steps one by one. `position` is the number of the
+/// last step taken, as counted by part 2.
+#[derive(Clone)]
+struct Floors {
+    steps: Vec<Step>,
+    position: usize,
+    floor: i32,
+}
+
+impl<'a> Simulation<'a> for Floors {
+    fn step(&mut self) -> Result<bool> {
+        let step = match self.steps.get(self.position) {
+            Some(step) => step,
+            None => return Ok(false),
+        };
 
-        Err(Error::ResultNotFound)
+        self.floor = climb(self.floor, step)?;
+        self.position += 1;
+
+        Ok(true)
+    }
+
+    fn fields(&self) -> Fields {
+        vec![("position", self.position.to_string()), ("floor", self.floor.to_string())]
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Simulation<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+impl Simulate for Solution {
+    fn simulate<'a>(&self, input: &'a str) -> Result<Box<dyn Simulation<'a> + 'a>> {
+        Ok(Box::new(Floors { steps: self.parse_input(input)?, position: 0, floor: 0 }))
+    }
+}
+
+/// `size` is the number of steps.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| if rng.chance(1, 2) { '(' } else { ')' })
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::aoc::test::{test_case, Part};
+    use crate::aoc::debug::Debugger;
+    use crate::aoc::test::{fuzz_corpus_case, generated_case, parse_error_case, stream_case, test_case, validate_case, Part};
 
     #[test]
     fn solve_part1_case_1() {
@@ -112,4 +206,45 @@ mod test {
     fn solve_part2_case_2() {
         test_case(Part::Two, Solution, "()())", 5);
     }
+
+    #[test]
+    fn generated_input_part1() {
+        generated_case(Part::One, Solution, 1_000);
+    }
+
+    #[test]
+    fn fuzz_corpus() {
+        fuzz_corpus_case(Solution, "y2015_day01");
+    }
+
+    #[test]
+    fn parse_error_position() {
+        parse_error_case(Part::One, Solution, "(()x(", 1, 4);
+    }
+
+    #[test]
+    fn validate_reports_every_bad_char() {
+        validate_case(Solution, "(x)y(", &[1, 1]);
+    }
+
+    #[test]
+    fn streaming_matches() {
+        for input in ["(()(()(", "()())", "  ((\n", "(()x(", "(\n)", "((", "", "())x", "())\n)x"] {
+            stream_case(Part::One, Solution, input);
+            stream_case(Part::Two, Solution, input);
+        }
+
+        let input = Solution.generate(&mut Rng::new(1), 1_000);
+        stream_case(Part::One, Solution, &input);
+        stream_case(Part::Two, Solution, &input);
+    }
+
+    #[test]
+    fn debugger_finds_the_basement() {
+        let mut debugger = Debugger::new(Solution.simulate("()())(").unwrap());
+        debugger.add_breakpoint("floor == -1".parse().unwrap());
+
+        assert_eq!(debugger.run().unwrap(), Some(0));
+        assert_eq!(debugger.fields(), vec![("position", "5".to_owned()), ("floor", "-1".to_owned())]);
+    }
 }
@@ -1,54 +1,107 @@
-use crate::aoc::{DaySolution, Error, Result};
+use crate::aoc::generator::InputGenerator;
+use crate::aoc::parse::{self, FromLine};
+use crate::aoc::registry::{aoc, aoc_parser};
+use crate::aoc::rng::Rng;
+use crate::aoc::{all_errors, Error, Result, Validate};
 
 pub struct Solution;
 
-impl Solution {
-    fn parse_input(&self, input: &str) -> Result<Vec<(u32, u32, u32)>> {
-        let mut data = Vec::new();
+#[derive(FromLine)]
+#[line("{l}x{w}x{h}")]
+struct Present {
+    l: u32,
+    w: u32,
+    h: u32,
+}
 
-        for line in input.lines() {
-            let present = line
-                .split("x")
-                .map(|n| n.parse::<u32>().map_err(|_| Error::InvalidInput))
-                .collect::<Result<Vec<_>>>()?;
+type Dimensions = (u32, u32, u32);
 
-            if present.len() != 3 {
-                return Err(Error::InvalidInput);
-            }
+fn presents(input: &str) -> impl Iterator<Item = Result<Dimensions>> + '_ {
+    parse::records(input).map(|p| p.map(|Present { l, w, h }| (l, w, h)))
+}
 
-            data.push((present[0], present[1], present[2]));
-        }
+#[aoc_parser(year = 2015, day = 2)]
+fn parse_input(input: &str) -> Result<Vec<Dimensions>> {
+    presents(input).collect()
+}
 
-        Ok(data)
+impl Validate for Solution {
+    fn validate(&self, input: &str) -> Result<()> {
+        all_errors(presents(input))
     }
 }
 
-impl DaySolution<u32> for Solution {
-    // TODO: refactor duplicated code
-    fn solve_part1(&self, input: &str) -> Result<u32> {
-        let result =
-            self.parse_input(input)?
+#[aoc(year = 2015, day = 2, part = 1)]
+fn paper(presents: &[Dimensions]) -> Result<u32> {
+    total(presents, |(l, w, h)| {
+        let sides = [l.checked_mul(w)?, l.checked_mul(h)?, w.checked_mul(h)?];
+        let smallest = *sides.iter().min()?;
+
+        sides
             .iter()
-            .map(|dims| {
-                let sides = [dims.0*dims.1, dims.0*dims.2, dims.1*dims.2];
-                return 2*sides.iter().sum::<u32>() + sides.iter().min().unwrap();
-            })
-            .sum::<u32>();
+            .try_fold(smallest, |acc, side| acc.checked_add(side.checked_mul(2)?))
+    })
+}
+
+#[aoc(year = 2015, day = 2, part = 2)]
+fn ribbon(presents: &[Dimensions]) -> Result<u32> {
+    total(presents, |(l, w, h)| {
+        let mut ds = [l, w, h];
+        ds.sort();
 
-        Ok(result)
+        let wrap = ds[0].checked_add(ds[1])?.checked_mul(2)?;
+        let bow = ds[0].checked_mul(ds[1])?.checked_mul(ds[2])?;
+        wrap.checked_add(bow)
+    })
+}
+
+// Presents too big for the result to fit in a u32 are rejected as invalid
+// input instead of overflowing.
+fn total(presents: &[Dimensions], needed: impl Fn(Dimensions) -> Option<u32>) -> Result<u32> {
+    presents
+        .iter()
+        .try_fold(0u32, |acc, &dims| acc.checked_add(needed(dims)?))
+        .ok_or(Error::InvalidInput)
+}
+
+/// `size` is the number of presents.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| format!("{}x{}x{}", rng.range(1, 30), rng.range(1, 30), rng.range(1, 30)))
+            .collect::<Vec<_>>()
+            .join("\n")
     }
+}
 
-    fn solve_part2(&self, input: &str) -> Result<u32> {
-        let result =
-            self.parse_input(input)?
-            .iter()
-            .map(|dims| {
-                let mut ds = vec![dims.0, dims.1, dims.2];
-                ds.sort();
-                return 2*(ds[0]+ds[1]) + ds[0]*ds[1]*ds[2];
-            })
-            .sum::<u32>();
-
-        Ok(result)
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::aoc::registry::Registered;
+    use crate::aoc::test::{fuzz_corpus_case, parse_error_case, test_case, validate_case, Part};
+
+    #[test]
+    fn solve_part1() {
+        test_case(Part::One, Registered::new(2015, 2), "2x3x4\n1x1x10", 58 + 43);
+    }
+
+    #[test]
+    fn solve_part2() {
+        test_case(Part::Two, Registered::new(2015, 2), "2x3x4\n1x1x10", 34 + 14);
+    }
+
+    #[test]
+    fn fuzz_corpus() {
+        fuzz_corpus_case(Registered::new(2015, 2), "y2015_day02");
+    }
+
+    #[test]
+    fn parse_error_position() {
+        parse_error_case(Part::One, Registered::new(2015, 2), "2x3x4\n1x1\n", 2, 4);
+    }
+
+    #[test]
+    fn validate_reports_every_bad_line() {
+        validate_case(Solution, "2x3x4\n1x1\n2xax4\n1x1x1", &[2, 3]);
     }
 }
@@ -1,96 +1,206 @@
-use crate::aoc::{DaySolution, Error, Result};
-use std::collections::HashSet;
-
-enum Move {
-    Up,
-    Right,
-    Down,
-    Left,
-}
+use crate::aoc::debug::{Simulate, Simulation};
+use crate::aoc::generator::InputGenerator;
+use crate::aoc::geom::{Direction, Point, SparseGrid};
+use crate::aoc::grid::Grid;
+use crate::aoc::rng::Rng;
+use crate::aoc::stream::{Chars, StreamingSolution};
+use crate::aoc::trace::Fields;
+use crate::aoc::viz::{Frame, FrameSink, Rgb, Visualize};
+use crate::aoc::{all_errors, DaySolution, Error, Result, Validate};
+use std::io::BufRead;
 
-impl TryFrom<char> for Move {
-    type Error = Error;
+pub struct Solution;
 
-    fn try_from(value: char) -> Result<Move> {
-        match value {
-            '^' => Ok(Move::Up),
-            '>' => Ok(Move::Right),
-            'v' => Ok(Move::Down),
-            '<' => Ok(Move::Left),
-            _ => Err(Error::InvalidInput),
-        }
+impl Solution {
+    fn parse_input(&self, input: &str) -> Result<Vec<Direction>> {
+        self.moves(input).collect()
     }
-}
 
-pub struct Solution;
+    fn moves<'a>(&self, input: &'a str) -> impl Iterator<Item = Result<Direction>> + 'a {
+        let trimmed = input.trim();
 
-impl Solution {
-    fn parse_input(&self, input: &str) -> Result<Vec<Move>> {
-        let data =
-            input
-            .trim()
-            .chars()
-            .map(|c| Move::try_from(c))
-            .collect::<Result<Vec<Move>>>()?;
+        trimmed
+            .char_indices()
+            .map(move |(i, c)| Direction::from_arrow(c).ok_or_else(|| {
+                Error::parse(input, &trimmed[i..i + c.len_utf8()], "one of `^`, `>`, `v` or `<`")
+            }))
+    }
+}
 
-        Ok(data)
+impl Validate for Solution {
+    fn validate(&self, input: &str) -> Result<()> {
+        all_errors(self.moves(input))
     }
 }
 
 impl DaySolution<usize> for Solution {
     fn solve_part1(&self, input: &str) -> Result<usize> {
-        let mut position = (0, 0);
+        visit(self.moves(input), 1)
+    }
+
+    fn solve_part2(&self, input: &str) -> Result<usize> {
+        visit(self.moves(input), 2)
+    }
+}
+
+impl StreamingSolution<usize> for Solution {
+    fn stream_part1(&self, input: &mut dyn BufRead) -> Result<usize> {
+        visit(stream_moves(input), 1)
+    }
+
+    fn stream_part2(&self, input: &mut dyn BufRead) -> Result<usize> {
+        visit(stream_moves(input), 2)
+    }
+}
+
+fn stream_moves<'a>(input: &'a mut dyn BufRead) -> impl Iterator<Item = Result<Direction>> + 'a {
+    let mut chars = Chars::new(input);
 
-        let mut seen = HashSet::new();
-        seen.insert(position);
+    std::iter::from_fn(move || {
+        let next = chars.next()?;
+        Some(next.and_then(|(c, position)| {
+            Direction::from_arrow(c).ok_or_else(|| chars.error(position, "one of `^`, `>`, `v` or `<`"))
+        }))
+    })
+}
+
+/// Counts the houses visited by `santas` taking turns to follow the moves.
+fn visit(moves: impl Iterator<Item = Result<Direction>>, santas: usize) -> Result<usize> {
+    let mut positions = vec![Point::ORIGIN; santas];
+
+    let mut houses = SparseGrid::new();
+    houses.insert(Point::ORIGIN, ());
+
+    for (i, m) in moves.enumerate() {
+        let current_pos = &mut positions[i % santas];
+
+        *current_pos = current_pos.step(m?);
+        houses.insert(*current_pos, ());
+    }
+
+    Ok(houses.len())
+}
+
+// at most this many frames for the whole trip
+const FRAMES: usize = 100;
+
+/// Santa delivering on his own, with houses getting lighter the more
+/// presents they get, and Santa in red.
+impl Visualize for Solution {
+    fn visualize(&self, input: &str, sink: &mut dyn FrameSink) -> Result<()> {
+        let moves = self.parse_input(input)?;
 
-        for m in self.parse_input(input)?.iter() {
-            position = match m {
-                Move::Up => (position.0, position.1+1),
-                Move::Right => (position.0+1, position.1),
-                Move::Down => (position.0, position.1-1),
-                Move::Left => (position.0-1, position.1),
-            };
+        let mut path = vec![Point::ORIGIN];
+        for &m in &moves {
+            path.push(path[path.len() - 1].step(m));
+        }
+
+        let mut trip = SparseGrid::new();
+        for &p in &path {
+            trip.insert(p, ());
+        }
+        let (min, max) = trip.bounds().ok_or(Error::InvalidInput)?;
+        let pixel = |p: Point| ((p.x - min.x) as usize, (p.y - min.y) as usize);
+
+        let (width, height) = pixel(max);
+        let mut image = Grid::new(width + 1, height + 1, Rgb::BLACK);
+        let mut visits = SparseGrid::new();
+        let every = moves.len().div_ceil(FRAMES).max(1);
+
+        for (i, &house) in path.iter().enumerate() {
+            let count = visits.entry(house).or_insert(0u32);
+            *count += 1;
+            image[pixel(house)] = Rgb::gray(96).mix(Rgb::WHITE, *count as f64 / 8.0);
 
-            seen.insert(position);
+            if i % every == 0 || i == moves.len() {
+                let mut frame = image.clone();
+                frame[pixel(house)] = Rgb::RED;
+                sink.frame(&Frame::Color(frame))?;
+            }
         }
 
-        Ok(seen.len())
+        Ok(())
     }
+}
 
-    fn solve_part2(&self, input: &str) -> Result<usize> {
-        let mut santa_pos = (0, 0);
-        let mut robot_pos = (0, 0);
-
-        let mut seen = HashSet::new();
-        seen.insert(santa_pos);
-
-        for (i, m) in self.parse_input(input)?.iter().enumerate() {
-            let current_pos =
-                if i % 2 == 0 {
-                    &mut santa_pos
-                } else {
-                    &mut robot_pos
-                };
-
-            *current_pos = match m {
-                Move::Up => (current_pos.0, current_pos.1+1),
-                Move::Right => (current_pos.0+1, current_pos.1),
-                Move::Down => (current_pos.0, current_pos.1-1),
-                Move::Left => (current_pos.0-1, current_pos.1),
-            };
-
-            seen.insert(*current_pos);
+// Larger trips are not drawn in the debugger.
+const MAX_RENDERED: i64 = 80;
+
+/// Santa delivering on his own, one move at a time.
+#[derive(Clone)]
+struct Trip {
+    moves: Vec<Direction>,
+    done: usize,
+    position: Point,
+    houses: SparseGrid<()>,
+}
+
+impl<'a> Simulation<'a> for Trip {
+    fn step(&mut self) -> Result<bool> {
+        let m = match self.moves.get(self.done) {
+            Some(&m) => m,
+            None => return Ok(false),
+        };
+
+        self.position = self.position.step(m);
+        self.houses.insert(self.position, ());
+        self.done += 1;
+
+        Ok(true)
+    }
+
+    fn fields(&self) -> Fields {
+        vec![
+            ("moves", self.done.to_string()),
+            ("x", self.position.x.to_string()),
+            ("y", self.position.y.to_string()),
+            ("houses", self.houses.len().to_string()),
+        ]
+    }
+
+    fn render(&self) -> Option<String> {
+        let (min, max) = self.houses.bounds()?;
+        if max.x - min.x >= MAX_RENDERED || max.y - min.y >= MAX_RENDERED {
+            return None;
+        }
+
+        let mut trip = SparseGrid::new();
+        for (p, _) in self.houses.iter() {
+            trip.insert(p, '#');
         }
+        trip.insert(self.position, '@');
+
+        Some(trip.render(|c| c.copied().unwrap_or('.')))
+    }
 
-        Ok(seen.len())
+    fn boxed_clone(&self) -> Box<dyn Simulation<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
+
+impl Simulate for Solution {
+    fn simulate<'a>(&self, input: &'a str) -> Result<Box<dyn Simulation<'a> + 'a>> {
+        let mut houses = SparseGrid::new();
+        houses.insert(Point::ORIGIN, ());
+
+        Ok(Box::new(Trip { moves: self.parse_input(input)?, done: 0, position: Point::ORIGIN, houses }))
+    }
+}
+
+/// `size` is the number of moves.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| *rng.choose(&['^', '>', 'v', '<']))
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::aoc::test::{test_case, Part};
+    use crate::aoc::debug::Debugger;
+    use crate::aoc::test::{fuzz_corpus_case, generated_case, parse_error_case, stream_case, test_case, Part};
 
     #[test]
     fn solve_part1_case_1() {
@@ -121,4 +231,53 @@ mod test {
     fn solve_part2_case_3() {
         test_case(Part::Two, Solution, "^v^v^v^v^v", 11);
     }
+
+    #[test]
+    fn generated_input_part2() {
+        generated_case(Part::Two, Solution, 1_000);
+    }
+
+    #[test]
+    fn fuzz_corpus() {
+        fuzz_corpus_case(Solution, "y2015_day03");
+    }
+
+    #[test]
+    fn parse_error_position() {
+        parse_error_case(Part::One, Solution, "^>v<?", 1, 5);
+    }
+
+    #[test]
+    fn streaming_matches() {
+        for input in ["^>v<", "^v^v^v^v^v", "\n^>\n", "^>v<?", "^ v", ""] {
+            stream_case(Part::One, Solution, input);
+            stream_case(Part::Two, Solution, input);
+        }
+
+        let input = Solution.generate(&mut Rng::new(1), 1_000);
+        stream_case(Part::Two, Solution, &input);
+    }
+
+    #[test]
+    fn trip_frames() {
+        let mut frames = vec![];
+        Solution.visualize("^>v<", &mut frames).unwrap();
+
+        assert_eq!(frames.len(), 5);
+        assert_eq!((frames[4].width(), frames[4].height()), (2, 2));
+        assert_eq!(frames[4].pixel((0, 1)), Rgb::RED);
+        assert_eq!(frames[4].pixel((0, 0)), Rgb::gray(96).mix(Rgb::WHITE, 1.0 / 8.0));
+    }
+
+    #[test]
+    fn debugger_steps_through_the_trip() {
+        let mut debugger = Debugger::new(Solution.simulate("^>v<<").unwrap());
+        debugger.goto(4).unwrap();
+        assert_eq!(debugger.render().unwrap(), "##\n@#");
+
+        debugger.add_breakpoint("houses == 5".parse().unwrap());
+        assert_eq!(debugger.run().unwrap(), Some(0));
+        assert!(debugger.back().unwrap());
+        assert_eq!(debugger.fields()[0], ("moves", "4".to_owned()));
+    }
 }
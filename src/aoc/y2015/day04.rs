@@ -1,11 +1,13 @@
-use crate::aoc::{DaySolution, Error, Result};
+use crate::aoc::generator::InputGenerator;
+use crate::aoc::rng::Rng;
+use crate::aoc::{DaySolution, Error, Result, Validate};
 use md5::Digest;
 
 pub struct Solution;
 
 impl Solution {
     fn solve_leading_zeros(&self, zeros_count: usize, input: &str) -> Result<u32> {
-        for i in 0u32..std::u32::MAX {
+        for i in 0u32..u32::MAX {
             let data = format!("{}{}", input, i);
             let hash = md5::Md5::digest(data.as_bytes());
 
@@ -21,12 +23,30 @@ impl Solution {
 impl DaySolution<u32> for Solution {
     fn solve_part1(&self, input: &str) -> Result<u32> {
         let data = input.trim();
-        Ok(self.solve_leading_zeros(5, &data)?)
+        self.solve_leading_zeros(5, data)
     }
 
     fn solve_part2(&self, input: &str) -> Result<u32> {
         let data = input.trim();
-        Ok(self.solve_leading_zeros(6, &data)?)
+        self.solve_leading_zeros(6, data)
+    }
+}
+
+// Any text is a valid input, there is nothing to parse.
+impl Validate for Solution {
+    fn validate(&self, _input: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `size` is the length of the secret key. There is no `generated_case`
+/// test for it: any key is valid, and solving one is the slow MD5 search
+/// the other tests are ignored for.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size.max(1))
+            .map(|_| (b'a' + rng.below(26) as u8) as char)
+            .collect()
     }
 }
 
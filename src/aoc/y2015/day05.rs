@@ -1,5 +1,7 @@
 use std::collections::HashSet;
-use crate::aoc::{DaySolution, Result};
+use crate::aoc::generator::InputGenerator;
+use crate::aoc::rng::Rng;
+use crate::aoc::{DaySolution, Result, Validate};
 
 pub struct Solution;
 
@@ -49,7 +51,7 @@ impl DaySolution<u32> for Solution {
                 last_char = c;
             }
 
-            return vowel >= 3 && twice;
+            vowel >= 3 && twice
         }, data);
 
         Ok(nice)
@@ -58,19 +60,17 @@ impl DaySolution<u32> for Solution {
     fn solve_part2(&self, input: &str) -> Result<u32> {
         let data = self.parse_input(input)?;
         let nice = self.nice_count(|s| {
-            if s.len() < 2 {
-                return false
-            }
+            let mut chars = s.chars();
+            let (mut last_2, mut last_1) = match (chars.next(), chars.next()) {
+                (Some(c1), Some(c2)) => (c1, c2),
+                _ => return false,
+            };
 
             let mut pairs = HashSet::new();
 
             let mut twice = false;
             let mut repeat = false;
 
-            let mut chars = s.chars();
-            let mut last_2 = chars.next().unwrap();
-            let mut last_1 = chars.next().unwrap();
-
             for c in chars {
                 if c == last_2 {
                     repeat = true;
@@ -85,17 +85,38 @@ impl DaySolution<u32> for Solution {
                 (last_2, last_1) = (last_1, c);
             }
 
-            return twice && repeat;
+            twice && repeat
         }, data);
 
         Ok(nice)
     }
 }
 
+// Any text is a valid input, there is nothing to parse.
+impl Validate for Solution {
+    fn validate(&self, _input: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// `size` is the number of strings.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                (0..16)
+                    .map(|_| (b'a' + rng.below(26) as u8) as char)
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::aoc::test::{test_case, Part};
+    use crate::aoc::test::{fuzz_corpus_case, generated_case, test_case, Part};
 
     #[test]
     fn solve_part1_case_1() {
@@ -141,4 +162,14 @@ mod test {
     fn solve_part2_case_4() {
         test_case(Part::Two, Solution, "ieodomkazucvgmuy", 0);
     }
+
+    #[test]
+    fn generated_input_part2() {
+        generated_case(Part::Two, Solution, 100);
+    }
+
+    #[test]
+    fn fuzz_corpus() {
+        fuzz_corpus_case(Solution, "y2015_day05");
+    }
 }
@@ -1,189 +1,334 @@
-use crate::aoc::{DaySolution, Error, Result};
+use crate::aoc::bitset::FixedBitSet;
+use crate::aoc::crosscheck::{Alternatives, Implementation};
+use crate::aoc::debug::{Simulate, Simulation};
+use crate::aoc::generator::InputGenerator;
+use crate::aoc::grid::Grid;
+use crate::aoc::interval::{Compression, Interval, Rect};
+use crate::aoc::parse::{self, Cursor};
+use crate::aoc::rng::Rng;
+use crate::aoc::stream::{self, StreamingSolution};
+use crate::aoc::trace::Fields;
+use crate::aoc::viz::{Frame, FrameSink, Visualize};
+use crate::aoc::{all_errors, DaySolution, Error, Result, Validate};
+use std::fmt;
+use std::io::BufRead;
+use std::rc::Rc;
 
 pub struct Solution;
 
+const SIDE: usize = 1000;
+
 enum Instruction {
-    On{from: (u32, u32), to: (u32, u32)},
-    Toggle{from: (u32, u32), to: (u32, u32)},
-    Off{from: (u32, u32), to: (u32, u32)},
+    On{from: (usize, usize), to: (usize, usize)},
+    Toggle{from: (usize, usize), to: (usize, usize)},
+    Off{from: (usize, usize), to: (usize, usize)},
 }
 
-#[derive(Clone)]
-enum Light {
+impl Instruction {
+    // The corners with the smallest and the biggest coordinates, whichever
+    // way round the input gives them.
+    fn corners(&self) -> ((usize, usize), (usize, usize)) {
+        let (Instruction::On{from, to} | Instruction::Toggle{from, to} | Instruction::Off{from, to}) = self;
+
+        ((from.0.min(to.0), from.1.min(to.1)), (from.0.max(to.0), from.1.max(to.1)))
+    }
+
+    fn rect(&self) -> Rect {
+        let ((x1, y1), (x2, y2)) = self.corners();
+
+        Rect::new(Interval::inclusive(x1 as i64, x2 as i64), Interval::inclusive(y1 as i64, y2 as i64))
+    }
+}
+
+/// As written in the input.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (action, from, to) = match self {
+            Instruction::On{from, to} => ("turn on", from, to),
+            Instruction::Toggle{from, to} => ("toggle", from, to),
+            Instruction::Off{from, to} => ("turn off", from, to),
+        };
+
+        write!(f, "{} {},{} through {},{}", action, from.0, from.1, to.0, to.1)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Action {
     On,
+    Toggle,
     Off,
 }
 
-struct InstructionsIterator<'a> {
-    lines: std::str::Lines<'a>,
+impl Solution {
+    fn instructions_iter<'a>(&self, input: &'a str) -> impl Iterator<Item = Result<Instruction>> + 'a {
+        parse::lines(input, parse_instruction)
+    }
+}
+
+fn parse_instruction(c: &mut Cursor) -> Result<Instruction> {
+    let action = c.one_of(&[
+        ("turn on", Action::On),
+        ("turn off", Action::Off),
+        ("toggle", Action::Toggle),
+    ])?;
+    let from = c.pair(",", parse_coordinate)?;
+    c.keyword("through")?;
+    let to = c.pair(",", parse_coordinate)?;
+
+    Ok(match action {
+        Action::On => Instruction::On{from, to},
+        Action::Toggle => Instruction::Toggle{from, to},
+        Action::Off => Instruction::Off{from, to},
+    })
 }
 
-impl<'a> InstructionsIterator<'a> {
-    fn new(input: &'a str) -> Self {
-        InstructionsIterator {
-            lines: input.trim().lines(),
-        }
+fn parse_coordinate(c: &mut Cursor) -> Result<usize> {
+    match c.integer_span::<usize>()? {
+        (n, _) if n < SIDE => Ok(n),
+        (_, span) => Err(c.error(span, format!("a number below {}", SIDE))),
+    }
+}
+
+impl Validate for Solution {
+    fn validate(&self, input: &str) -> Result<()> {
+        all_errors(self.instructions_iter(input))
     }
+}
 
-    fn parse_ranges(fields: &[&str], range1_idx: usize, range2_idx: usize) -> Result<((u32, u32), (u32, u32))> {
-        let start_range = fields[range1_idx].split(',').collect::<Vec<_>>();
-        if start_range.len() != 2 {
-            return Err(Error::InvalidInput);
+impl DaySolution<usize> for Solution {
+    fn solve_part1(&self, input: &str) -> Result<usize> {
+        let mut lights = FixedBitSet::new(SIDE * SIDE);
+        for ins in self.instructions_iter(input) {
+            switch_lights(&mut lights, &ins?);
         }
 
-        let start_row = start_range[0].parse::<u32>().map_err(|_| Error::InvalidInput)?;
-        let start_column = start_range[1].parse::<u32>().map_err(|_| Error::InvalidInput)?;
+        Ok(lights.count_ones())
+    }
 
-        let end_range = fields[range2_idx].split(',').collect::<Vec<_>>();
-        if end_range.len() != 2 {
-            return Err(Error::InvalidInput);
+    fn solve_part2(&self, input: &str) -> Result<usize> {
+        let mut brightness = Grid::new(SIDE, SIDE, 0u32);
+        for ins in self.instructions_iter(input) {
+            adjust_brightness(&mut brightness, &ins?);
         }
 
-        let end_row = end_range[0].parse::<u32>().map_err(|_| Error::InvalidInput)?;
-        let end_column = end_range[1].parse::<u32>().map_err(|_| Error::InvalidInput)?;
+        total_brightness(&brightness)
+    }
+}
 
-        Ok(((start_row, start_column), (end_row, end_column)))
+impl Alternatives<usize> for Solution {
+    fn part1_implementations(&self) -> Vec<Implementation<usize>> {
+        vec![
+            ("bitset", |input| Solution.solve_part1(input)),
+            ("compressed", |input| {
+                compressed(input, false, |on, ins| {
+                    *on = match ins {
+                        Instruction::On{..} => true,
+                        Instruction::Off{..} => false,
+                        Instruction::Toggle{..} => !*on,
+                    }
+                }, |&on| on as u64)
+            }),
+        ]
+    }
+
+    fn part2_implementations(&self) -> Vec<Implementation<usize>> {
+        vec![
+            ("grid", |input| Solution.solve_part2(input)),
+            ("compressed", |input| {
+                compressed(input, 0u32, |b, ins| {
+                    *b = match ins {
+                        Instruction::On{..} => *b + 1,
+                        Instruction::Off{..} => b.saturating_sub(1),
+                        Instruction::Toggle{..} => *b + 2,
+                    }
+                }, |&b| b as u64)
+            }),
+        ]
     }
 }
 
-impl<'a> std::iter::Iterator for InstructionsIterator<'a> {
-    type Item = Result<Instruction>;
+impl StreamingSolution<usize> for Solution {
+    fn stream_part1(&self, input: &mut dyn BufRead) -> Result<usize> {
+        let mut lights = FixedBitSet::new(SIDE * SIDE);
+        for ins in stream::lines(input, parse_instruction) {
+            switch_lights(&mut lights, &ins?);
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let line = self.lines.next()?;
+        Ok(lights.count_ones())
+    }
 
-        let fields = line.split_whitespace().collect::<Vec<_>>();
-        if fields.len() < 4 {
-            return Some(Err(Error::InvalidInput));
+    fn stream_part2(&self, input: &mut dyn BufRead) -> Result<usize> {
+        let mut brightness = Grid::new(SIDE, SIDE, 0u32);
+        for ins in stream::lines(input, parse_instruction) {
+            adjust_brightness(&mut brightness, &ins?);
         }
 
-        match (fields[0], fields[1]) {
-            ("turn", "on") =>
-                return match Self::parse_ranges(&fields, 2, 4) {
-                    Ok(((start_row, start_column), (end_row, end_column))) =>
-                        Some(Ok(Instruction::On{
-                            from: (start_row, start_column),
-                            to: (end_row, end_column),
-                        })),
-                    _ => return Some(Err(Error::InvalidInput)),
-                },
-            ("turn", "off") =>
-                return match Self::parse_ranges(&fields, 2, 4) {
-                    Ok(((start_row, start_column), (end_row, end_column))) =>
-                        Some(Ok(Instruction::Off{
-                            from: (start_row, start_column),
-                            to: (end_row, end_column),
-                        })),
-                    _ => return Some(Err(Error::InvalidInput)),
-                },
-            ("toggle", _) =>
-                return match Self::parse_ranges(&fields, 1, 3) {
-                    Ok(((start_row, start_column), (end_row, end_column))) =>
-                        Some(Ok(Instruction::Toggle{
-                            from: (start_row, start_column),
-                            to: (end_row, end_column),
-                        })),
-                    _ => return Some(Err(Error::InvalidInput)),
-                },
-            (_, _) => return Some(Err(Error::InvalidInput)),
+        total_brightness(&brightness)
+    }
+}
+
+// The lights are one bit each, row after row, so each row of the
+// rectangle changes a word at a time.
+fn switch_lights(lights: &mut FixedBitSet, ins: &Instruction) {
+    let ((x1, y1), (x2, y2)) = ins.corners();
+    for row in (y1..=y2).map(|y| y * SIDE + x1..y * SIDE + x2 + 1) {
+        match ins {
+            Instruction::On{..} => lights.set_range(row),
+            Instruction::Off{..} => lights.clear_range(row),
+            Instruction::Toggle{..} => lights.toggle_range(row),
         }
     }
 }
 
-impl Solution {
-    fn instructions_iter<'a>(&self, input: &'a str) -> InstructionsIterator<'a> {
-        InstructionsIterator::new(input)
+fn adjust_brightness(brightness: &mut Grid<u32>, ins: &Instruction) {
+    match *ins {
+        Instruction::On{from, to} =>
+            brightness.rect_mut(from, to).for_each(|b| *b += 1),
+        Instruction::Off{from, to} =>
+            brightness.rect_mut(from, to).for_each(|b| *b = b.saturating_sub(1)),
+        Instruction::Toggle{from, to} =>
+            brightness.rect_mut(from, to).for_each(|b| *b += 2),
     }
 }
 
-impl DaySolution<usize> for Solution {
-    fn solve_part1(&self, input: &str) -> Result<usize> {
-        let mut lights = vec![Light::Off; 1_000_000];
+// Works on the segments between the edges of the rectangles, weighted by
+// their area, instead of on every light. `amount` is what one light in a
+// segment adds to the answer.
+fn compressed<T: Clone>(
+    input: &str,
+    start: T,
+    change: impl Fn(&mut T, &Instruction),
+    amount: impl Fn(&T) -> u64,
+) -> Result<usize> {
+    let instructions = Solution.instructions_iter(input).collect::<Result<Vec<_>>>()?;
+
+    let rects = instructions.iter().map(Instruction::rect).collect::<Vec<_>>();
+    let xs = Compression::of_intervals(rects.iter().map(|r| &r.x));
+    let ys = Compression::of_intervals(rects.iter().map(|r| &r.y));
+
+    let mut segments = Grid::new(xs.len(), ys.len(), start);
+    for (ins, rect) in instructions.iter().zip(&rects) {
+        let (cx, cy) = xs.segments(&rect.x).zip(ys.segments(&rect.y)).ok_or(Error::InvalidInput)?;
+        segments
+            .rect_mut((cx.start, cy.start), (cx.end - 1, cy.end - 1))
+            .for_each(|s| change(s, ins));
+    }
 
+    segments
+        .iter()
+        .map(|((x, y), s)| amount(s) * xs.segment(x).len() * ys.segment(y).len())
+        .sum::<u64>()
+        .try_into()
+        .map_err(|_| Error::InvalidInput)
+}
+
+fn total_brightness(brightness: &Grid<u32>) -> Result<usize> {
+    brightness
+        .values()
+        .map(|&b| b as u64)
+        .sum::<u64>()
+        .try_into()
+        .map_err(|_| Error::InvalidInput)
+}
+
+/// The lights after every instruction, white when on.
+impl Visualize for Solution {
+    fn visualize(&self, input: &str, sink: &mut dyn FrameSink) -> Result<()> {
+        let mut lights = FixedBitSet::new(SIDE * SIDE);
         for ins in self.instructions_iter(input) {
-            match ins? {
-                Instruction::On{from: (fr, fc), to: (tr, tc)} => {
-                    for row in fr..=tr {
-                        for col in fc..=tc {
-                            lights[(row*1000+col) as usize] = Light::On;
-                        }
-                    }
-                },
-                Instruction::Off{from: (fr, fc), to: (tr, tc)} => {
-                    for row in fr..=tr {
-                        for col in fc..=tc {
-                            lights[(row*1000+col) as usize] = Light::Off;
-                        }
-                    }
-                },
-                Instruction::Toggle{from: (fr, fc), to: (tr, tc)} => {
-                    for row in fr..=tr {
-                        for col in fc..=tc {
-                            lights[(row*1000+col) as usize] =
-                                match lights[(row*1000+col) as usize] {
-                                    Light::On => Light::Off,
-                                    Light::Off => Light::On,
-                                }
-                        }
-                    }
-                },
-            }
+            switch_lights(&mut lights, &ins?);
+            sink.frame(&Frame::Gray(Grid::from_fn(SIDE, SIDE, |x, y| {
+                if lights.get(y * SIDE + x) { 255 } else { 0 }
+            })))?;
         }
 
-        let result =
-            lights
-            .into_iter()
-            .filter(|l| if let Light::On = l { true } else { false })
-            .collect::<Vec<_>>()
-            .len();
+        Ok(())
+    }
+}
+
+/// Both sets of lights, following one instruction at a time.
+#[derive(Clone)]
+struct LightShow {
+    instructions: Rc<[Instruction]>,
+    done: usize,
+    lights: FixedBitSet,
+    brightness: Grid<u32>,
+}
 
-        Ok(result)
+impl<'a> Simulation<'a> for LightShow {
+    fn step(&mut self) -> Result<bool> {
+        let ins = match self.instructions.get(self.done) {
+            Some(ins) => ins,
+            None => return Ok(false),
+        };
+
+        switch_lights(&mut self.lights, ins);
+        adjust_brightness(&mut self.brightness, ins);
+        self.done += 1;
+
+        Ok(true)
     }
 
-    fn solve_part2(&self, input: &str) -> Result<usize> {
-        let mut brightness: Vec::<u32> = vec![0; 1_000_000];
+    fn fields(&self) -> Fields {
+        let last = match self.done {
+            0 => String::new(),
+            n => self.instructions[n - 1].to_string(),
+        };
 
-        for ins in self.instructions_iter(input) {
-            match ins? {
-                Instruction::On{from: (fr, fc), to: (tr, tc)} => {
-                    for row in fr..=tr {
-                        for col in fc..=tc {
-                            brightness[(row*1000+col) as usize] += 1;
-                        }
-                    }
-                },
-                Instruction::Off{from: (fr, fc), to: (tr, tc)} => {
-                    for row in fr..=tr {
-                        for col in fc..=tc {
-                            if brightness[(row*1000+col) as usize] > 0 {
-                                brightness[(row*1000+col) as usize] -= 1;
-                            }
-                        }
-                    }
-                },
-                Instruction::Toggle{from: (fr, fc), to: (tr, tc)} => {
-                    for row in fr..=tr {
-                        for col in fc..=tc {
-                            brightness[(row*1000+col) as usize] += 2;
-                        }
-                    }
-                },
-            }
-        }
+        vec![
+            ("instructions", self.done.to_string()),
+            ("last", last),
+            ("lit", self.lights.count_ones().to_string()),
+            ("brightness", self.brightness.values().map(|&b| b as u64).sum::<u64>().to_string()),
+        ]
+    }
+
+    fn boxed_clone(&self) -> Box<dyn Simulation<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
 
-        let result =
-            brightness
-            .into_iter()
-            .sum::<u32>() as usize;
+impl Simulate for Solution {
+    fn simulate<'a>(&self, input: &'a str) -> Result<Box<dyn Simulation<'a> + 'a>> {
+        Ok(Box::new(LightShow {
+            instructions: self.instructions_iter(input).collect::<Result<_>>()?,
+            done: 0,
+            lights: FixedBitSet::new(SIDE * SIDE),
+            brightness: Grid::new(SIDE, SIDE, 0),
+        }))
+    }
+}
 
-        Ok(result)
+/// `size` is the number of instructions.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                let action = *rng.choose(&["turn on", "turn off", "toggle"]);
+                let (fr, tr) = (rng.range(0, 999), rng.range(0, 999));
+                let (fc, tc) = (rng.range(0, 999), rng.range(0, 999));
+
+                format!(
+                    "{} {},{} through {},{}",
+                    action,
+                    fr.min(tr),
+                    fc.min(tc),
+                    fr.max(tr),
+                    fc.max(tc))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::aoc::test::{test_case, Part};
+    use crate::aoc::crosscheck::{cross_check, Config};
+    use crate::aoc::debug::Debugger;
+    use crate::aoc::test::{fuzz_corpus_case, generated_case, parse_error_case, stream_case, test_case, validate_case, Part};
+    use crate::aoc::viz::Rgb;
 
     #[test]
     fn solve_part1_case_1() {
@@ -222,4 +367,90 @@ mod test {
     fn solve_part2_case_2() {
         test_case(Part::Two, Solution, "toggle 0,0 through 999,999", 2_000_000);
     }
+
+    #[test]
+    fn generated_input_part1() {
+        generated_case(Part::One, Solution, 20);
+    }
+
+    #[test]
+    fn cross_check_part1() {
+        let result = cross_check(&Solution, &Solution.part1_implementations(), &Config::default());
+        assert!(result.is_ok(), "{}", result.unwrap_err());
+    }
+
+    #[test]
+    fn cross_check_part2() {
+        let result = cross_check(&Solution, &Solution.part2_implementations(), &Config::default());
+        assert!(result.is_ok(), "{}", result.unwrap_err());
+    }
+
+    #[test]
+    fn fuzz_corpus() {
+        fuzz_corpus_case(Solution, "y2015_day06");
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let input = "
+            turn on 0,0 through 2,2
+            toggle 0,0 through 1000,0
+        ";
+        parse_error_case(Part::One, Solution, input, 3, 32);
+    }
+
+    #[test]
+    fn validate_reports_every_bad_line() {
+        let input = "
+            turn on 0,0 through 2,2
+            toggle 0,0 thru 1,1
+            turn off 0,0 through 9,9
+            turn up 0,0 through 9,9
+        ";
+        validate_case(Solution, input, &[3, 5]);
+    }
+
+    #[test]
+    fn streaming_matches() {
+        let inputs = [
+            "turn on 0,0 through 999,999\ntoggle 0,0 through 999,0\nturn off 499,499 through 500,500",
+            "\n  toggle 5,5 through 2,2\n\nturn off 3,3 through 3,3\n",
+            "turn on 0,0 through 9,9\nturn up 0,0 through 9,9",
+            "toggle 0,0 through 1000,3",
+        ];
+        for input in inputs {
+            stream_case(Part::One, Solution, input);
+            stream_case(Part::Two, Solution, input);
+        }
+
+        let input = Solution.generate(&mut Rng::new(1), 50);
+        stream_case(Part::One, Solution, &input);
+        stream_case(Part::Two, Solution, &input);
+    }
+
+    #[test]
+    fn frame_per_instruction() {
+        let mut frames = vec![];
+        Solution.visualize("turn on 0,0 through 1,1\ntoggle 1,1 through 2,2", &mut frames).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].pixel((0, 0)), Rgb::WHITE);
+        assert_eq!(frames[1].pixel((1, 1)), Rgb::BLACK);
+        assert_eq!(frames[1].pixel((2, 2)), Rgb::WHITE);
+    }
+
+    #[test]
+    fn debugger_shows_each_instruction() {
+        let input = "turn on 0,0 through 9,9\ntoggle 5,5 through 14,14\nturn off 0,0 through 0,0";
+        let mut debugger = Debugger::new(Solution.simulate(input).unwrap());
+        debugger.add_breakpoint("lit == 149".parse().unwrap());
+
+        assert_eq!(debugger.run().unwrap(), Some(0));
+        assert_eq!(debugger.fields(), vec![
+            ("instructions", "3".to_owned()),
+            ("last", "turn off 0,0 through 0,0".to_owned()),
+            ("lit", "149".to_owned()),
+            ("brightness", "299".to_owned()),
+        ]);
+    }
 }
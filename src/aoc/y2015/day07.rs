@@ -1,8 +1,17 @@
-use crate::aoc::{DaySolution, Error, Result};
+use crate::aoc::debug::{Simulate, Simulation};
+use crate::aoc::generator::InputGenerator;
+use crate::aoc::memo::Memo;
+use crate::aoc::parse::{self, Cursor};
+use crate::aoc::rng::Rng;
+use crate::aoc::trace::{self, Fields};
+use crate::aoc::{all_errors, DaySolution, Error, Result, Validate};
 use std::collections::HashMap;
+use std::rc::Rc;
 
 pub struct Solution;
 
+// variant names mirror the gate keywords used in the puzzle input
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug)]
 enum Instruction<'a> {
     ValueSignal(u16, &'a str),
@@ -28,149 +37,276 @@ impl<'a> Instruction<'a> {
             Instruction::RSHIFT(_, _, output) => output,
         }
     }
-}
 
-struct InstructionsIterator<'a> {
-    lines: std::str::Lines<'a>,
-}
+    fn inputs(&self) -> Vec<&'a str> {
+        match *self {
+            Instruction::ValueSignal(_, _) => vec![],
+            Instruction::WireSignal(input, _)
+            | Instruction::NOT(input, _)
+            | Instruction::AND1(input, _)
+            | Instruction::LSHIFT(input, _, _)
+            | Instruction::RSHIFT(input, _, _) => vec![input],
+            Instruction::AND(input1, input2, _) | Instruction::OR(input1, input2, _) => vec![input1, input2],
+        }
+    }
 
-impl<'a> InstructionsIterator<'a> {
-    fn new(input: &'a str) -> Self {
-        InstructionsIterator {
-            lines: input.trim().lines(),
+    /// The signal on the output, given a way to get the signal of the
+    /// inputs.
+    fn eval(&self, mut value: impl FnMut(&'a str) -> Result<u16>) -> Result<u16> {
+        match *self {
+            Instruction::ValueSignal(v, _) => Ok(v),
+            Instruction::WireSignal(input, _) => value(input),
+            Instruction::NOT(input, _) => Ok(!value(input)?),
+            Instruction::AND(input1, input2, _) => Ok(value(input1)? & value(input2)?),
+            Instruction::AND1(input, _) => Ok(1 & value(input)?),
+            Instruction::OR(input1, input2, _) => Ok(value(input1)? | value(input2)?),
+            Instruction::LSHIFT(input, amount, _) => Ok(value(input)? << amount),
+            Instruction::RSHIFT(input, amount, _) => Ok(value(input)? >> amount),
         }
     }
 }
 
-impl<'a> std::iter::Iterator for InstructionsIterator<'a> {
-    type Item = Result<Instruction<'a>>;
+#[derive(Clone, Copy)]
+enum Gate {
+    Wire,
+    And,
+    Or,
+    LShift,
+    RShift,
+}
+
+fn instructions(input: &str) -> impl Iterator<Item = Result<Instruction<'_>>> {
+    parse::lines(input, |c| {
+        if c.optional(|c| c.keyword("NOT")).is_some() {
+            let input = c.identifier()?;
+            c.literal("->")?;
+            return Ok(Instruction::NOT(input, c.identifier()?));
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let line = self.lines.next()?;
-        let fields = line.split_whitespace().collect::<Vec<_>>();
+        let input1 = c.identifier()?;
+        let gate = c.one_of(&[
+            ("->", Gate::Wire),
+            ("AND", Gate::And),
+            ("OR", Gate::Or),
+            ("LSHIFT", Gate::LShift),
+            ("RSHIFT", Gate::RShift),
+        ])?;
 
-        match &fields[..] {
-            &[signal, "->", wire] =>
-                match signal.parse::<u16>() {
-                    Ok(v) => Some(Ok(Instruction::ValueSignal(v, wire))),
+        let instruction = match gate {
+            Gate::Wire => {
+                let output = c.identifier()?;
+                match input1.parse::<u16>() {
+                    Ok(v) => Instruction::ValueSignal(v, output),
 
                     // If it cannot be parse as a number, it is treated as
                     // a wire name
-                    Err(_) => Some(Ok(Instruction::WireSignal(signal, wire))),
-                },
-            &["NOT", input, "->", output] =>
-                Some(Ok(Instruction::NOT(
-                            input,
-                            output))),
-            &["1", "AND", input, "->", output] =>
-                Some(Ok(Instruction::AND1(
-                            input,
-                            output))),
-            &[input1, "AND", input2, "->", output] =>
-                Some(Ok(Instruction::AND(
-                            input1,
-                            input2,
-                            output))),
-            &[input1, "OR", input2, "->", output] =>
-                Some(Ok(Instruction::OR(
-                            input1,
-                            input2,
-                            output))),
-            &[input1, "LSHIFT", input2, "->", output] =>
-                match input2.parse::<u16>() {
-                    Ok(v) => Some(Ok(Instruction::LSHIFT(
-                                input1,
-                                v,
-                                output))),
-                    Err(_) => Some(Err(Error::InvalidInput)),
-                },
-            &[input1, "RSHIFT", input2, "->", output] =>
-                match input2.parse::<u16>() {
-                    Ok(v) => Some(Ok(Instruction::RSHIFT(
-                                input1,
-                                v,
-                                output))),
-                    Err(_) => Some(Err(Error::InvalidInput)),
-                },
-            _ => Some(Err(Error::InvalidInput)),
-        }
+                    Err(_) => Instruction::WireSignal(input1, output),
+                }
+            }
+            Gate::And if input1 == "1" => {
+                let input2 = c.identifier()?;
+                c.literal("->")?;
+                Instruction::AND1(input2, c.identifier()?)
+            }
+            Gate::And | Gate::Or => {
+                let input2 = c.identifier()?;
+                c.literal("->")?;
+                let output = c.identifier()?;
+                match gate {
+                    Gate::And => Instruction::AND(input1, input2, output),
+                    _ => Instruction::OR(input1, input2, output),
+                }
+            }
+            Gate::LShift | Gate::RShift => {
+                let amount = parse_shift(c)?;
+                c.literal("->")?;
+                let output = c.identifier()?;
+                match gate {
+                    Gate::LShift => Instruction::LSHIFT(input1, amount, output),
+                    _ => Instruction::RSHIFT(input1, amount, output),
+                }
+            }
+        };
+
+        Ok(instruction)
+    })
+}
+
+fn parse_shift(c: &mut Cursor) -> Result<u16> {
+    match c.integer_span::<u16>()? {
+        (v, _) if v < 16 => Ok(v),
+        (_, span) => Err(c.error(span, "a shift amount below 16")),
     }
 }
 
+// Every wire with the instruction that gives it its signal.
+fn circuit(input: &str) -> Result<HashMap<&str, Instruction<'_>>> {
+    let mut out_inst = HashMap::new();
+    for instruction in instructions(input) {
+        let inst = instruction?;
+        out_inst.insert(inst.output(), inst);
+    }
+
+    Ok(out_inst)
+}
+
 impl DaySolution<u16> for Solution {
     fn solve_part1(&self, input: &str) -> Result<u16> {
-        let mut out_inst: HashMap<&str, Instruction> = HashMap::new();
-        for instruction in InstructionsIterator::new(input) {
-            let inst = instruction?;
-            out_inst.insert(inst.output(), inst);
-        }
+        let out_inst = circuit(input)?;
 
-        let mut memo: HashMap<&str, u16> = HashMap::new();
-        value_for("a", &out_inst, &mut memo)
+        signal("a", &out_inst)
     }
 
     fn solve_part2(&self, input: &str) -> Result<u16> {
         let value_for_a = self.solve_part1(input)?;         
 
-        let mut out_inst: HashMap<&str, Instruction> = HashMap::new();
-        for instruction in InstructionsIterator::new(input) {
-            let inst = instruction?;
-            out_inst.insert(inst.output(), inst);
-        }
+        let mut out_inst = circuit(input)?;
 
         out_inst.insert("b", Instruction::ValueSignal(value_for_a, "b"));
 
-        let mut memo: HashMap<&str, u16> = HashMap::new();
-        value_for("a", &out_inst, &mut memo)
+        signal("a", &out_inst)
+    }
+}
+
+impl Validate for Solution {
+    fn validate(&self, input: &str) -> Result<()> {
+        all_errors(instructions(input))
     }
 }
 
-fn value_for<'a>(
+// Evaluated with an explicit stack, as generated circuits can have chains
+// of wires too long for the call stack. `on_resolved` is called for every
+// wire `wire` depends on, after its inputs.
+fn resolve<'a>(
     wire: &'a str,
-    out_inst: &'a HashMap<&str, Instruction>,
-    memo: &mut HashMap<&'a str, u16>,
+    out_inst: &HashMap<&'a str, Instruction<'a>>,
+    mut on_resolved: impl FnMut(&'a str, u16),
 ) -> Result<u16> {
-    if let Some(val) = memo.get(wire) {
-        return Ok(*val);
+    Memo::new().evaluate(
+        wire,
+        |w| Ok(out_inst.get(w).ok_or(Error::ResultNotFound)?.inputs()),
+        |&w, inputs| {
+            // `eval` asks for its inputs in the order `inputs` lists them
+            let mut inputs = inputs.iter().copied();
+            let value = out_inst[w].eval(|_| inputs.next().ok_or(Error::ResultNotFound))?;
+
+            trace::event("wire", || vec![("name", w.to_owned()), ("value", value.to_string())]);
+            on_resolved(w, value);
+            Ok(value)
+        })
+}
+
+fn signal<'a>(wire: &'a str, out_inst: &HashMap<&'a str, Instruction<'a>>) -> Result<u16> {
+    resolve(wire, out_inst, |_, _| ())
+}
+
+/// The circuit of part 1 resolving one wire at a time, in an order where
+/// the inputs of a wire always come before it.
+#[derive(Clone)]
+struct Resolution<'a> {
+    circuit: Rc<HashMap<&'a str, Instruction<'a>>>,
+    order: Rc<[&'a str]>,
+    values: HashMap<&'a str, u16>,
+}
+
+impl<'a> Simulation<'a> for Resolution<'a> {
+    fn step(&mut self) -> Result<bool> {
+        let wire = match self.order.get(self.values.len()) {
+            Some(&wire) => wire,
+            None => return Ok(false),
+        };
+
+        let values = &self.values;
+        let value = self.circuit[wire].eval(|input| values.get(input).copied().ok_or(Error::ResultNotFound))?;
+        self.values.insert(wire, value);
+
+        Ok(true)
     }
 
-    let val = match out_inst.get(wire) {
-        // the last field is the output, same as wire
-        Some(Instruction::ValueSignal(value, _)) => Ok(*value),
+    fn fields(&self) -> Fields {
+        let (wire, value) = match self.values.len() {
+            0 => ("", String::new()),
+            n => (self.order[n - 1], self.values[self.order[n - 1]].to_string()),
+        };
 
-        Some(Instruction::WireSignal(input, _)) =>
-            Ok(value_for(input, out_inst, memo)?),
+        vec![
+            ("resolved", self.values.len().to_string()),
+            ("wire", wire.to_owned()),
+            ("value", value),
+        ]
+    }
 
-        Some(Instruction::NOT(input, _)) =>
-            Ok(!(value_for(input, out_inst, memo)?)),
+    fn boxed_clone(&self) -> Box<dyn Simulation<'a> + 'a> {
+        Box::new(self.clone())
+    }
+}
 
-        Some(Instruction::AND(input1, input2, _)) =>
-            Ok(value_for(input1, out_inst, memo)? & value_for(input2, out_inst, memo)?),
+impl Simulate for Solution {
+    fn simulate<'a>(&self, input: &'a str) -> Result<Box<dyn Simulation<'a> + 'a>> {
+        let circuit = circuit(input)?;
+        let mut order = vec![];
+        resolve("a", &circuit, |wire, _| order.push(wire))?;
 
-        Some(Instruction::AND1(input, _)) =>
-            Ok(1 & value_for(input, out_inst, memo)?),
+        Ok(Box::new(Resolution { circuit: Rc::new(circuit), order: order.into(), values: HashMap::new() }))
+    }
+}
 
-        Some(Instruction::OR(input1, input2, _)) =>
-            Ok(value_for(input1, out_inst, memo)? | value_for(input2, out_inst, memo)?),
+/// `size` is the number of wires. Every gate only reads wires defined
+/// before it, so the circuit is acyclic, and wire `a` is always the last
+/// one to be defined.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let count = size.max(2);
 
-        Some(Instruction::LSHIFT(input1, input2, _)) =>
-            Ok(value_for(input1, out_inst, memo)? << input2),
+        // wire names in spreadsheet column order, skipping "a" so it can be
+        // used for the final wire
+        let mut wires = (1..count)
+            .map(|i| {
+                let mut name = Vec::new();
+                let mut n = i + 1;
+                while n > 0 {
+                    n -= 1;
+                    name.push(b'a' + (n % 26) as u8);
+                    n /= 26;
+                }
+                name.into_iter().rev().map(|c| c as char).collect::<String>()
+            })
+            .collect::<Vec<_>>();
+        wires.push("a".to_owned());
 
-        Some(Instruction::RSHIFT(input1, input2, _)) =>
-            Ok(value_for(input1, out_inst, memo)? >> input2),
+        let sources = (count / 10).max(2).min(count - 1);
 
-        None => Err(Error::ResultNotFound),
-    }?;
+        let mut lines = Vec::with_capacity(count);
+        for (i, output) in wires.iter().enumerate() {
+            if i < sources {
+                lines.push(format!("{} -> {}", rng.below(1 << 16), output));
+                continue;
+            }
 
-    memo.insert(wire, val);
+            let input1 = rng.choose(&wires[..i]);
+            let input2 = rng.choose(&wires[..i]);
+            let line = match rng.below(7) {
+                0 => format!("{} -> {}", input1, output),
+                1 => format!("NOT {} -> {}", input1, output),
+                2 => format!("{} AND {} -> {}", input1, input2, output),
+                3 => format!("1 AND {} -> {}", input1, output),
+                4 => format!("{} OR {} -> {}", input1, input2, output),
+                5 => format!("{} LSHIFT {} -> {}", input1, rng.range(1, 15), output),
+                _ => format!("{} RSHIFT {} -> {}", input1, rng.range(1, 15), output),
+            };
+            lines.push(line);
+        }
 
-    Ok(val)
+        rng.shuffle(&mut lines);
+        lines.join("\n")
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::aoc::test::{test_case, Part};
+    use crate::aoc::debug::Debugger;
+    use crate::aoc::test::{fuzz_corpus_case, generated_case, parse_error_case, test_case, validate_case, Part};
 
     #[test]
     fn solve_part1_case_1() {
@@ -207,4 +343,81 @@ mod test {
         ";
         test_case(Part::Two, Solution, input, 4);
     }
+
+    #[test]
+    fn generated_input_part2() {
+        generated_case(Part::Two, Solution, 500);
+    }
+
+    #[test]
+    fn fuzz_corpus() {
+        fuzz_corpus_case(Solution, "y2015_day07");
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let input = "
+            123 -> x
+            x LSHIFT 16 -> a
+        ";
+        parse_error_case(Part::One, Solution, input, 3, 22);
+    }
+
+    #[test]
+    fn validate_reports_every_bad_line() {
+        let input = "
+            123 -> x
+            x LSHIFT 16 -> a
+            x XOR y -> b
+            NOT x -> c
+        ";
+        validate_case(Solution, input, &[3, 4]);
+    }
+
+    #[test]
+    fn debugger_stops_on_a_wire() {
+        let input = "
+            123 -> x
+            456 -> y
+            x AND y -> d
+            x OR y -> e
+            d OR e -> a
+        ";
+        let mut debugger = Debugger::new(Solution.simulate(input).unwrap());
+        debugger.add_breakpoint("wire == e".parse().unwrap());
+
+        assert_eq!(debugger.run().unwrap(), Some(0));
+        assert_eq!(debugger.fields(), vec![
+            ("resolved", "4".to_owned()),
+            ("wire", "e".to_owned()),
+            ("value", "507".to_owned()),
+        ]);
+
+        debugger.goto(usize::MAX).unwrap();
+        assert_eq!(debugger.fields()[2], ("value", "507".to_owned()));
+    }
+
+    #[test]
+    fn long_chains_of_wires() {
+        let mut lines = vec!["1 -> w0".to_owned()];
+        lines.extend((1..100_000).map(|i| format!("w{} -> w{}", i - 1, i)));
+        lines.push("w99999 -> a".to_owned());
+
+        test_case(Part::One, Solution, &lines.join("\n"), 1);
+    }
+
+    #[test]
+    fn loops_are_reported() {
+        let input = "
+            b -> a
+            c AND d -> b
+            1 -> d
+            NOT b -> c
+        ";
+
+        match Solution.solve_part1(input) {
+            Err(Error::Cycle(wires)) => assert_eq!(wires, ["b", "c", "b"]),
+            other => panic!("expected a cycle, got: {:?}", other),
+        }
+    }
 }
@@ -1,50 +1,27 @@
-use crate::aoc::{DaySolution, Error, Result};
+use crate::aoc::generator::InputGenerator;
+use crate::aoc::rng::Rng;
+use crate::aoc::{all_errors, DaySolution, Error, Result, Validate};
 
 pub struct Solution;
 
+const ESCAPES: &str = "an escape sequence like `\\\\`, `\\\"` or `\\x27`";
+
 impl DaySolution<u32> for Solution {
     fn solve_part1(&self, input: &str) -> Result<u32> {
         let mut result = 0;
 
         for line in input.trim().lines() {
-            let chars = line.trim().chars().collect::<Vec<_>>();
-            let code_len = chars.len();
-            let mut memory_len = 0;
-            let mut i = 0;
-
-            while i < chars.len() {
-                match chars.get(i) {
-                    Some('\\') => {
-                        i += 1;
-                        match chars.get(i) {
-                            Some('\\') => memory_len += 1,
-                            Some('"') => memory_len += 1,
-                            Some('x') => {
-                                i += 2;
-                                memory_len += 1
-                            },
-                            Some(_) => return Err(Error::InvalidInput),
-                            None => return Err(Error::InvalidInput),
-                        }
-                    },
-                    Some('"') => memory_len += 0,
-                    Some(_) => memory_len += 1,
-                    None => break,
-                }
-
-                i += 1;
-            }
-
-            result += code_len - memory_len;
+            let line = line.trim();
+            result += line.chars().count() - memory_len(input, line)?;
         }
 
-        Ok(result.try_into().unwrap())
+        result.try_into().map_err(|_| Error::InvalidInput)
     }
 
     fn solve_part2(&self, input: &str) -> Result<u32> {
         let mut result = 0;
         for line in input.trim().lines() {
-            let code_len = line.len();
+            let code_len = line.chars().count();
             let encoded_len: u32 =
                line
                .chars()
@@ -62,10 +39,72 @@ impl DaySolution<u32> for Solution {
     }
 }
 
+impl Validate for Solution {
+    fn validate(&self, input: &str) -> Result<()> {
+        all_errors(input.trim().lines().map(|line| memory_len(input, line.trim())))
+    }
+}
+
+fn memory_len(input: &str, line: &str) -> Result<usize> {
+    let chars = line.char_indices().collect::<Vec<_>>();
+    let mut memory_len = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars.get(i).map(|&(_, c)| c) {
+            Some('\\') => {
+                let start = chars[i].0;
+                i += 1;
+                match chars.get(i) {
+                    Some((_, '\\')) => memory_len += 1,
+                    Some((_, '"')) => memory_len += 1,
+                    Some((_, 'x')) => {
+                        i += 2;
+                        memory_len += 1
+                    },
+                    Some(&(end, c)) =>
+                        return Err(Error::parse(input, &line[start..end + c.len_utf8()], ESCAPES)),
+                    None =>
+                        return Err(Error::parse(input, &line[start..], ESCAPES)),
+                }
+            },
+            Some('"') => memory_len += 0,
+            Some(_) => memory_len += 1,
+            None => break,
+        }
+
+        i += 1;
+    }
+
+    Ok(memory_len)
+}
+
+/// `size` is the number of string literals.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                let mut literal = String::from("\"");
+                for _ in 0..rng.range(0, 20) {
+                    match rng.below(10) {
+                        0 => literal.push_str("\\\\"),
+                        1 => literal.push_str("\\\""),
+                        2 => literal.push_str(&format!("\\x{:02x}", rng.below(256))),
+                        _ => literal.push((b'a' + rng.below(26) as u8) as char),
+                    }
+                }
+                literal.push('"');
+                literal
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::aoc::test::{test_case, Part};
+    use crate::aoc::test::{fuzz_corpus_case, generated_case, parse_error_case, test_case, Part};
 
     #[test]
     fn solve_part1_case_1() {
@@ -130,4 +169,19 @@ mod test {
 
         test_case(Part::Two, Solution, input, 19);
     }
+
+    #[test]
+    fn generated_input_part1() {
+        generated_case(Part::One, Solution, 100);
+    }
+
+    #[test]
+    fn fuzz_corpus() {
+        fuzz_corpus_case(Solution, "y2015_day08");
+    }
+
+    #[test]
+    fn parse_error_position() {
+        parse_error_case(Part::One, Solution, "\"abc\\q\"", 1, 5);
+    }
 }
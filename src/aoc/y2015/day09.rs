@@ -1,6 +1,12 @@
-use std::collections::{HashMap, HashSet};
-
-use crate::aoc::{DaySolution, Error, Result};
+use crate::aoc::combinatorics::permutations;
+use crate::aoc::crosscheck::{Alternatives, Implementation};
+use crate::aoc::generator::{unique_names, InputGenerator};
+use crate::aoc::graph::{Graph, Interner};
+use crate::aoc::parse::{self, FromLine};
+use crate::aoc::rng::Rng;
+use crate::aoc::search::{self, SearchState, SeenKeys, Unvisited};
+use crate::aoc::trace;
+use crate::aoc::{all_errors, DaySolution, Error, Result, Validate};
 
 type City = String;
 
@@ -8,91 +14,197 @@ pub struct Solution;
 
 impl DaySolution<u32> for Solution {
     fn solve_part1(&self, input: &str) -> Result<u32> {
-        best_route(input, |x, y| y < x)
+        held_karp(input, u32::min)
     }
 
     fn solve_part2(&self, input: &str) -> Result<u32> {
-        best_route(input, |x, y| y > x)
+        held_karp(input, u32::max)
+    }
+}
+
+impl Alternatives<u32> for Solution {
+    fn part1_implementations(&self) -> Vec<Implementation<u32>> {
+        vec![
+            ("permutations", |input| best_route(input, |x, y| y < x)),
+            ("held-karp", |input| held_karp(input, u32::min)),
+            ("search", shortest_route_search),
+        ]
+    }
+
+    fn part2_implementations(&self) -> Vec<Implementation<u32>> {
+        vec![
+            ("permutations", |input| best_route(input, |x, y| y > x)),
+            ("held-karp", |input| held_karp(input, u32::max)),
+            ("search", longest_route_search),
+        ]
     }
 }
 
+impl Validate for Solution {
+    fn validate(&self, input: &str) -> Result<()> {
+        all_errors(parse_distances(input))
+    }
+}
+
+// Tries every ordering of the cities, kept as the reference the faster
+// implementations are cross-checked against.
 fn best_route(input: &str, cmp: impl Fn(u32, u32) -> bool) -> Result<u32> {
-    let mut distances: HashMap<(City, City), u32> = HashMap::new();
-    let mut cities: HashSet<City> = HashSet::new();
-    for line in input.trim().lines() {
-        let (city1, city2, distance) = parse_distance(line)?;
-        cities.insert(city1.clone());
-        cities.insert(city2.clone());
-        distances.insert((city1.clone(), city2.clone()), distance);
-        distances.insert((city2, city1), distance);
-    }
-
-    let perms = permutations(&mut cities.into_iter().collect::<Vec<_>>());
-    let mut result = trip_distance(&perms[0], &distances);
-
-    for perm in perms {
-        let distance = trip_distance(&perm, &distances);
-        if cmp(result, distance) {
-            result = distance
+    let (graph, cities) = parse_graph(input)?;
+
+    let mut perms = permutations((0..graph.len()).collect());
+    let mut result = None;
+
+    while let Some(perm) = perms.next_permutation() {
+        let distance = trip_distance(perm, &graph)?;
+        trace::event("route", || {
+            let route = perm.iter().map(|&c| cities.name(c).as_str()).collect::<Vec<_>>();
+            vec![("cities", route.join(",")), ("distance", distance.to_string())]
+        });
+
+        if result.is_none_or(|r| cmp(r, distance)) {
+            result = Some(distance)
         }
     }
 
-    Ok(result)
+    result.ok_or(Error::InvalidInput)
 }
 
-fn trip_distance(perms: &[City], distances: &HashMap<(City, City), u32>) -> u32 {
+fn trip_distance(perms: &[usize], graph: &Graph<u32>) -> Result<u32> {
     perms
         .iter()
-        .zip(&perms[1..])
-        .map(|(c1, c2)| distances.get(&(c1.clone(), c2.clone())).unwrap())
-        .sum()
+        .zip(perms.iter().skip(1))
+        .try_fold(0u32, |total, (&c1, &c2)| {
+            let distance = graph.weight(c1, c2).ok_or(Error::InvalidInput)?;
+            total.checked_add(distance).ok_or(Error::InvalidInput)
+        })
 }
 
-fn parse_distance(line: &str) -> Result<(City, City, u32)> {
-    let fields = line.split_whitespace().collect::<Vec<_>>();
-    let city1 = fields.get(0).ok_or(Error::InvalidInput)?;
-    let city2 = fields.get(2).ok_or(Error::InvalidInput)?;
-    let distance = fields
-        .get(4)
-        .ok_or(Error::InvalidInput)?
-        .parse()
-        .map_err(|_| Error::InvalidInput)?;
+// See `Graph::tours` for how the routes are found.
+fn held_karp(input: &str, pick: fn(u32, u32) -> u32) -> Result<u32> {
+    let (graph, cities) = parse_graph(input)?;
+    let ends = graph.best_hamiltonian_path_ends(pick)?;
+
+    for (city, distance) in ends.iter().enumerate() {
+        if let Some(distance) = distance {
+            trace::event("route", || vec![("end", cities.name(city).clone()), ("distance", distance.to_string())]);
+        }
+    }
 
-    Ok((city1.to_string(), city2.to_string(), distance))
+    ends.into_iter().flatten().reduce(pick).ok_or(Error::ResultNotFound)
 }
 
-fn permutations(cities: &mut [City]) -> Vec<Vec<City>> {
-    let count = cities.len();
+// A route being planned, the cities visited so far as a bitmask and where
+// it currently ends. It starts nowhere, so the first move can be to any
+// city for free.
+struct Route<'a> {
+    graph: &'a Graph<u32>,
+    current: Option<usize>,
+    visited: u32,
+}
+
+impl<'a> Route<'a> {
+    fn start(graph: &'a Graph<u32>) -> Result<Self> {
+        if graph.len() > u32::BITS as usize {
+            return Err(Error::InvalidInput);
+        }
 
-    if count == 0 {
-        return vec![];
+        Ok(Route { graph, current: None, visited: 0 })
     }
 
-    if count == 1 {
-        let elem = cities[0].clone();
-        return vec![vec![elem]];
+    fn to(&self, city: usize) -> Self {
+        Route { graph: self.graph, current: Some(city), visited: self.visited | (1 << city) }
     }
+}
 
-    let mut result = vec![];
-    for idx in (0..count).rev() {
-        let last = cities[count - 1].clone();
+impl SearchState for Route<'_> {
+    type Cost = u32;
 
-        let mut perms = permutations(&mut cities[0..count - 1]);
-        while let Some(mut perm) = perms.pop() {
-            perm.push(last.clone());
-            result.push(perm);
+    fn successors(&self) -> Vec<(Self, u32)> {
+        match self.current {
+            None => (0..self.graph.len()).map(|city| (self.to(city), 0)).collect(),
+            Some(current) => self
+                .graph
+                .neighbors(current)
+                .filter(|&(city, _)| self.visited & (1 << city) == 0)
+                .map(|(city, distance)| (self.to(city), distance))
+                .collect(),
         }
+    }
 
-        cities.swap(count - 1, idx);
+    fn is_goal(&self) -> bool {
+        self.visited.count_ones() as usize == self.graph.len()
     }
+}
+
+// Routes that reach the same city through the same cities are the same as
+// far as the rest of the trip is concerned.
+fn shortest_route_search(input: &str) -> Result<u32> {
+    let (graph, _) = parse_graph(input)?;
+    let (found, _) = search::dijkstra(Route::start(&graph)?, &mut SeenKeys::new(|r: &Route| (r.current, r.visited)));
+
+    found.map(|(_, distance)| distance).ok_or(Error::ResultNotFound)
+}
 
-    result
+fn longest_route_search(input: &str) -> Result<u32> {
+    let (graph, _) = parse_graph(input)?;
+    let (found, _) = search::exhaustive(Route::start(&graph)?, &mut Unvisited, u32::max);
+
+    found.map(|(_, distance)| distance).ok_or(Error::ResultNotFound)
+}
+
+// Every city must have a distance to every other one, so that any order of
+// visiting them is a valid route.
+fn parse_graph(input: &str) -> Result<(Graph<u32>, Interner<City>)> {
+    let mut cities = Interner::new();
+    let mut graph = Graph::undirected();
+    for distance in parse_distances(input) {
+        let (city1, city2, distance) = distance?;
+        let (i, j) = (cities.intern(city1), cities.intern(city2));
+        graph.add_edge(i, j, distance);
+    }
+
+    let complete = (0..graph.len())
+        .all(|i| (0..graph.len()).all(|j| i == j || graph.weight(i, j).is_some()));
+    if graph.is_empty() || !complete {
+        return Err(Error::InvalidInput);
+    }
+
+    Ok((graph, cities))
+}
+
+#[derive(FromLine)]
+#[line("{from} to {to} = {distance}")]
+struct Distance {
+    from: City,
+    to: City,
+    distance: u32,
+}
+
+fn parse_distances(input: &str) -> impl Iterator<Item = Result<(City, City, u32)>> + '_ {
+    parse::records(input).map(|d| d.map(|Distance { from, to, distance }| (from, to, distance)))
+}
+
+/// `size` is the number of cities, with a distance for every pair of them.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let cities = unique_names(rng, size.max(2));
+
+        let mut lines = vec![];
+        for (i, city1) in cities.iter().enumerate() {
+            for city2 in cities.iter().skip(i + 1) {
+                lines.push(format!("{} to {} = {}", city1, city2, rng.range(1, 200)));
+            }
+        }
+
+        lines.join("\n")
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::aoc::test::{test_case, Part};
+    use crate::aoc::crosscheck::{cross_check, Config};
+    use crate::aoc::test::{fuzz_corpus_case, generated_case, parse_error_case, test_case, validate_case, Part};
 
     #[test]
     fn solve_part1_case_1() {
@@ -113,4 +225,79 @@ mod test {
         ";
         test_case(Part::Two, Solution, input, 982);
     }
+
+    #[test]
+    fn generated_input_part1() {
+        generated_case(Part::One, Solution, 6);
+    }
+
+    #[test]
+    fn cross_check_part1() {
+        let config = Config { max_size: 6, ..Config::default() };
+        let result = cross_check(&Solution, &Solution.part1_implementations(), &config);
+        assert!(result.is_ok(), "{}", result.unwrap_err());
+    }
+
+    #[test]
+    fn cross_check_part2() {
+        let config = Config { max_size: 6, ..Config::default() };
+        let result = cross_check(&Solution, &Solution.part2_implementations(), &config);
+        assert!(result.is_ok(), "{}", result.unwrap_err());
+    }
+
+    #[test]
+    fn search_only_expands_each_subset_once() {
+        let input = Solution.generate(&mut Rng::new(1), 8);
+        let (graph, _) = parse_graph(&input).unwrap();
+
+        let mut seen = SeenKeys::new(|r: &Route| (r.current, r.visited));
+        let (_, pruned) = search::dijkstra(Route::start(&graph).unwrap(), &mut seen);
+        let (_, all) = search::exhaustive(Route::start(&graph).unwrap(), &mut Unvisited, u32::min);
+
+        assert_eq!(shortest_route_search(&input).unwrap(), held_karp(&input, u32::min).unwrap());
+        // at most once per city and set of cities visited before it
+        assert!(pruned.expanded <= 1 + (8 << 8));
+        assert!(all.expanded > pruned.expanded);
+    }
+
+    #[test]
+    fn fuzz_corpus() {
+        fuzz_corpus_case(Solution, "y2015_day09");
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let input = "
+            London to Dublin = 464
+            London to Belfast = far
+        ";
+        parse_error_case(Part::One, Solution, input, 3, 33);
+    }
+
+    #[test]
+    fn validate_reports_every_bad_line() {
+        let input = "
+            London to Dublin = 464
+            London to Belfast = far
+            Dublin Belfast = 141
+        ";
+        validate_case(Solution, input, &[3, 4]);
+    }
+
+    #[test]
+    fn traces_route_ends() {
+        let input = "London to Dublin = 464\nLondon to Belfast = 518\nDublin to Belfast = 141";
+        let (_, events) = trace::capture(|| Solution.solve_part1(input));
+
+        if trace::ENABLED {
+            let events = events.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+            assert_eq!(events, vec![
+                "route end=London distance=605",
+                "route end=Dublin distance=659",
+                "route end=Belfast distance=605",
+            ]);
+        } else {
+            assert!(events.is_empty());
+        }
+    }
 }
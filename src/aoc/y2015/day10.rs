@@ -1,29 +1,54 @@
-use crate::aoc::{DaySolution, Result};
+use crate::aoc::generator::InputGenerator;
+use crate::aoc::registry::aoc;
+use crate::aoc::rng::Rng;
+use crate::aoc::{all_errors, Error, Result, Validate};
 
 pub struct Solution;
 
-impl DaySolution<u32> for Solution {
-    fn solve_part1(&self, input: &str) -> Result<u32> {
-        Ok(repeated_look_and_say(input, 40))
-    }
+#[aoc(year = 2015, day = 10, part = 1)]
+fn part1(input: &str) -> Result<u32> {
+    Ok(repeated_look_and_say(parse_input(input)?, 40))
+}
 
-    fn solve_part2(&self, input: &str) -> Result<u32> {
-        Ok(repeated_look_and_say(input, 50))
+#[aoc(year = 2015, day = 10, part = 2)]
+fn part2(input: &str) -> Result<u32> {
+    Ok(repeated_look_and_say(parse_input(input)?, 50))
+}
+
+impl Validate for Solution {
+    fn validate(&self, input: &str) -> Result<()> {
+        all_errors(digit_errors(input).into_iter().map(Err::<(), _>))
     }
 }
 
-fn look_and_say(input: &str) -> String {
-    if input.len() == 0 {
-        return "".to_owned();
+fn parse_input(input: &str) -> Result<&str> {
+    if let Some(e) = digit_errors(input).into_iter().next() {
+        return Err(e);
     }
 
+    Ok(input.trim())
+}
+
+fn digit_errors(input: &str) -> Vec<Error> {
+    let data = input.trim();
+
+    data.char_indices()
+        .filter(|(_, c)| !c.is_ascii_digit())
+        .map(|(i, c)| Error::parse(input, &data[i..i + c.len_utf8()], "a digit"))
+        .collect()
+}
+
+fn look_and_say(input: &str) -> String {
     let mut result = String::new();
     let mut count = 1;
 
-    let mut chars = input.trim().chars();
-    let mut prev_char = chars.next().unwrap();
+    let mut chars = input.chars();
+    let mut prev_char = match chars.next() {
+        Some(c) => c,
+        None => return "".to_owned(),
+    };
 
-    while let Some(c) = chars.next() {
+    for c in chars {
         if c == prev_char {
             count += 1;
         } else {
@@ -48,9 +73,19 @@ fn repeated_look_and_say(input: &str, count: u8) -> u32 {
         result_string.len() as u32
 }
 
+/// `size` is the number of digits in the starting sequence.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size.max(1))
+            .map(|_| (b'1' + rng.below(3) as u8) as char)
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::aoc::test::{test_case, Part};
+    use crate::aoc::registry::Registered;
+    use crate::aoc::test::{fuzz_corpus_case, parse_error_case, test_case, Part};
 
     use super::*;
 
@@ -86,12 +121,22 @@ mod test {
 
     #[test]
     fn solve_part1() {
-        test_case(Part::One, Solution, "1", 82350)
+        test_case(Part::One, Registered::new(2015, 10), "1", 82350)
     }
 
     #[test]
     #[ignore = "long running test"]
     fn solve_part2() {
-        test_case(Part::Two, Solution, "1", 1166642)
+        test_case(Part::Two, Registered::new(2015, 10), "1", 1166642)
+    }
+
+    #[test]
+    fn fuzz_corpus() {
+        fuzz_corpus_case(Registered::new(2015, 10), "y2015_day10");
+    }
+
+    #[test]
+    fn parse_error_position() {
+        parse_error_case(Part::One, Registered::new(2015, 10), "12a3", 1, 3);
     }
 }
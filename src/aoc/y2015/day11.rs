@@ -1,10 +1,13 @@
-use crate::aoc::{DaySolution, Result};
+use crate::aoc::generator::InputGenerator;
+use crate::aoc::rng::Rng;
+use crate::aoc::trace;
+use crate::aoc::{all_errors, DaySolution, Error, Result, Validate};
 
 pub struct Solution;
 
 impl DaySolution<String> for Solution {
     fn solve_part1(&self, input: &str) -> Result<String> {
-        let mut password = input.trim().chars().collect::<Vec<_>>();
+        let mut password = parse_input(input)?;
 
         to_next_valid_password(&mut password);
 
@@ -12,7 +15,7 @@ impl DaySolution<String> for Solution {
     }
 
     fn solve_part2(&self, input: &str) -> Result<String> {
-        let mut password = input.trim().chars().collect::<Vec<_>>();
+        let mut password = parse_input(input)?;
 
         to_next_valid_password(&mut password);
         to_next_valid_password(&mut password);
@@ -21,9 +24,44 @@ impl DaySolution<String> for Solution {
     }
 }
 
+impl Validate for Solution {
+    fn validate(&self, input: &str) -> Result<()> {
+        all_errors(password_errors(input).into_iter().map(Err::<(), _>))
+    }
+}
+
+fn parse_input(input: &str) -> Result<Vec<char>> {
+    if let Some(e) = password_errors(input).into_iter().next() {
+        return Err(e);
+    }
+
+    Ok(input.trim().chars().collect())
+}
+
+fn password_errors(input: &str) -> Vec<Error> {
+    let data = input.trim();
+
+    let mut errors = data
+        .char_indices()
+        .filter(|(_, c)| !c.is_ascii_lowercase())
+        .map(|(i, c)| Error::parse(input, &data[i..i + c.len_utf8()], "a lowercase letter"))
+        .collect::<Vec<_>>();
+
+    // a valid password needs at least a straight of three letters and two
+    // different pairs, shorter ones would never stop incrementing
+    if data.len() < 5 {
+        errors.push(Error::parse(input, data, "a password of at least 5 letters"));
+    }
+
+    errors
+}
+
 fn to_next_valid_password(password: &mut [char]) {
     to_next_password(password);
-    while !is_valid(&password) {
+    while let Some(rule) = broken_rule(password) {
+        trace::event("rejected", || {
+            vec![("password", password.iter().collect()), ("rule", rule.to_owned())]
+        });
         to_next_password(password)
     }
 }
@@ -43,17 +81,16 @@ fn to_next_password(input: &mut [char]) {
     }
 }
 
-fn is_valid(password: &[char]) -> bool {
+/// The first rule the password does not follow, if any.
+fn broken_rule(password: &[char]) -> Option<&'static str> {
     let mut straight = false;
     let mut pairs = 0;
     let mut prev1 = 0; // out of range initial char
     let mut prev2 = 0; // out of range initial char
 
-    for i in 0..password.len() {
-        let chr = password[i];
-
+    for &chr in password {
         if chr == 'i' || chr == 'o' || chr == 'l' {
-            return false;
+            return Some("no i, o or l");
         }
 
         if prev2 + 1 == prev1 && prev1 + 1 == chr as u8 {
@@ -67,12 +104,27 @@ fn is_valid(password: &[char]) -> bool {
         (prev1, prev2) = (chr as u8, prev1);
     }
 
-    straight && pairs >= 2
+    if !straight {
+        Some("a straight of three letters")
+    } else if pairs < 2 {
+        Some("two different pairs")
+    } else {
+        None
+    }
+}
+
+/// `size` is the password length, which is never less than 8.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size.max(8))
+            .map(|_| (b'a' + rng.below(26) as u8) as char)
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::aoc::test::{test_case, Part};
+    use crate::aoc::test::{fuzz_corpus_case, generated_case, parse_error_case, test_case, Part};
 
     use super::*;
 
@@ -86,4 +138,34 @@ mod test {
     fn solve_part1_case2() {
         test_case(Part::One, Solution, "ghijklmn", "ghjaabcc".to_owned())
     }
+
+    #[test]
+    fn fuzz_corpus() {
+        fuzz_corpus_case(Solution, "y2015_day11");
+    }
+
+    #[test]
+    fn parse_error_position() {
+        parse_error_case(Part::One, Solution, "abcD", 1, 4);
+    }
+
+    // ignored by default: from a random password the next valid one can be
+    // millions of increments away, which takes a while in a debug build
+    #[test]
+    #[ignore]
+    fn generated_input_part1() {
+        generated_case(Part::One, Solution, 8);
+    }
+
+    #[test]
+    fn traces_rejected_passwords() {
+        let (_, events) = trace::capture(|| Solution.solve_part1("abcdefgh"));
+
+        if trace::ENABLED {
+            assert_eq!(events[0].to_string(), "rejected password=abcdefgi rule=no i, o or l");
+            assert!(events.iter().all(|e| e.kind == "rejected"));
+        } else {
+            assert!(events.is_empty());
+        }
+    }
 }
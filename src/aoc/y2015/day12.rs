@@ -1,5 +1,7 @@
-use crate::aoc::DaySolution;
-use crate::aoc::Result;
+use crate::aoc::generator::InputGenerator;
+use crate::aoc::rng::Rng;
+use crate::aoc::{all_errors, DaySolution, Validate};
+use crate::aoc::{Error, ParseError, Result};
 use regex::Regex;
 use serde_json::Value;
 
@@ -7,28 +9,50 @@ pub struct Solution;
 
 impl DaySolution<i64> for Solution {
     fn solve_part1(&self, input: &str) -> Result<i64> {
-        let re = Regex::new(r"-?\d+").unwrap();
-
-        let mut result = 0;
-        for cap in re.captures_iter(input) {
-            result += cap[0].parse::<i64>().unwrap();
+        let mut result: i64 = 0;
+        for n in numbers(input) {
+            result = result.checked_add(n?).ok_or(Error::InvalidInput)?;
         }
 
         Ok(result)
     }
 
     fn solve_part2(&self, input: &str) -> Result<i64> {
-        let value: Value = serde_json::from_str(input).unwrap();
+        json_sum(&parse_json(input)?)
+    }
+}
+
+impl Validate for Solution {
+    fn validate(&self, input: &str) -> Result<()> {
+        let numbers = numbers(input).into_iter().map(|n| n.map(|_| ()));
+        let json = std::iter::once(parse_json(input).map(|_| ()));
 
-        Ok(json_sum(&value))
+        all_errors(numbers.chain(json))
     }
 }
 
-fn json_sum(value: &Value) -> i64 {
+fn numbers(input: &str) -> Vec<Result<i64>> {
+    let re = Regex::new(r"-?\d+").unwrap();
+
+    re.find_iter(input)
+        .map(|m| {
+            m.as_str()
+                .parse::<i64>()
+                .map_err(|_| Error::parse(input, m.as_str(), "a number that fits in 64 bits"))
+        })
+        .collect()
+}
+
+fn parse_json(input: &str) -> Result<Value> {
+    serde_json::from_str(input)
+        .map_err(|e| Error::Parse(ParseError::at_position(input, e.line(), e.column(), "valid JSON")))
+}
+
+fn json_sum(value: &Value) -> Result<i64> {
     match value {
-        Value::Number(n) => n.as_i64().unwrap(),
+        Value::Number(n) => n.as_i64().ok_or(Error::InvalidInput),
         Value::Object(kvs) => {
-            let mut result = 0;
+            let mut result: i64 = 0;
             for (_, v) in kvs.iter() {
                 match v {
                     Value::String(s) => {
@@ -37,21 +61,64 @@ fn json_sum(value: &Value) -> i64 {
                             break;
                         }
                     }
-                    _ => result += json_sum(v),
+                    _ => result = result.checked_add(json_sum(v)?).ok_or(Error::InvalidInput)?,
                 };
             }
 
-            result
+            Ok(result)
         }
-        Value::Array(arr) => arr.iter().map(|v| json_sum(v)).sum(),
-        _ => 0,
+        Value::Array(arr) => arr
+            .iter()
+            .try_fold(0i64, |acc, v| acc.checked_add(json_sum(v)?).ok_or(Error::InvalidInput)),
+        _ => Ok(0),
+    }
+}
+
+/// `size` is roughly the number of values in the JSON document.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let mut budget = size.max(1);
+        random_json(rng, &mut budget, 0).to_string()
+    }
+}
+
+fn random_json(rng: &mut Rng, budget: &mut usize, depth: usize) -> Value {
+    *budget = budget.saturating_sub(1);
+
+    let container = *budget > 0 && depth < 8 && rng.chance(1, 3);
+    if !container {
+        return match rng.below(4) {
+            0 => Value::from(*rng.choose(&["red", "green", "blue"])),
+            _ => Value::from(rng.range(-100, 100)),
+        };
+    }
+
+    let len = rng.range(1, 6);
+    if rng.chance(1, 2) {
+        let mut values = vec![];
+        for _ in 0..len {
+            if *budget == 0 {
+                break;
+            }
+            values.push(random_json(rng, budget, depth + 1));
+        }
+        Value::Array(values)
+    } else {
+        let mut values = serde_json::Map::new();
+        for i in 0..len {
+            if *budget == 0 {
+                break;
+            }
+            values.insert(format!("k{}", i), random_json(rng, budget, depth + 1));
+        }
+        Value::Object(values)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::aoc::test::{test_case, Part};
+    use crate::aoc::test::{fuzz_corpus_case, generated_case, parse_error_case, test_case, Part};
 
     #[test]
     fn solve_part1_case1() {
@@ -107,4 +174,19 @@ mod test {
     fn solve_part2_case4() {
         test_case(Part::Two, Solution, "[1,\"red\",5]", 6);
     }
+
+    #[test]
+    fn generated_input_part2() {
+        generated_case(Part::Two, Solution, 1_000);
+    }
+
+    #[test]
+    fn fuzz_corpus() {
+        fuzz_corpus_case(Solution, "y2015_day12");
+    }
+
+    #[test]
+    fn parse_error_position() {
+        parse_error_case(Part::Two, Solution, "[1,{\"a\":}]", 1, 9);
+    }
 }
@@ -1,123 +1,274 @@
-use std::collections::{HashMap, HashSet};
-
-use crate::aoc::{DaySolution, Error, Result};
+use crate::aoc::combinatorics::permutations;
+use crate::aoc::crosscheck::{Alternatives, Implementation};
+use crate::aoc::generator::{unique_names, InputGenerator};
+use crate::aoc::graph::{Graph, Interner};
+use crate::aoc::grid::Grid;
+use crate::aoc::parse::{self, Cursor, FromLine, Token};
+use crate::aoc::rng::Rng;
+use crate::aoc::search::{self, SearchState, Unvisited};
+use crate::aoc::viz::{self, Frame, FrameSink, Rgb, Visualize};
+use crate::aoc::{all_errors, DaySolution, Error, Result, Validate};
 
 pub struct Solution;
 
 impl DaySolution<i32> for Solution {
     fn solve_part1(&self, input: &str) -> Result<i32> {
-        let (people, relations) = parse_input(input)?;
-
-        best_arrangement_score(&people, &relations)
+        best_arrangement_score(&parse_input(input)?)
     }
 
     fn solve_part2(&self, input: &str) -> Result<i32> {
-        let (mut people, mut relations) = parse_input(input)?;
+        best_arrangement_score(&with_me(parse_input(input)?))
+    }
+}
 
-        for p in people.iter() {
-            relations.insert(("", p), 0);
-            relations.insert((p, ""), 0);
-        }
-        people.push("");
+impl Alternatives<i32> for Solution {
+    fn part1_implementations(&self) -> Vec<Implementation<i32>> {
+        vec![
+            ("held-karp", |input| best_arrangement_score(&parse_input(input)?)),
+            ("search", |input| best_arrangement_search(&parse_input(input)?)),
+        ]
+    }
 
-        best_arrangement_score(&people, &relations)
+    fn part2_implementations(&self) -> Vec<Implementation<i32>> {
+        vec![
+            ("held-karp", |input| best_arrangement_score(&with_me(parse_input(input)?))),
+            ("search", |input| best_arrangement_search(&with_me(parse_input(input)?))),
+        ]
     }
 }
 
-fn parse_input(input: &str) -> Result<(Vec<&str>, HashMap<(&str, &str), i32>)> {
-    let mut people = HashSet::new();
-    let mut relations = HashMap::new();
+impl Validate for Solution {
+    fn validate(&self, input: &str) -> Result<()> {
+        all_errors(parse_relations(input))
+    }
+}
+
+// An edge from one person to another is how their happiness changes when
+// sitting next to them.
+fn parse_input(input: &str) -> Result<Graph<i32>> {
+    let mut people = Interner::new();
+    let mut relations = Graph::directed();
 
-    for line in input.trim().lines() {
-        let fields = line.split_whitespace().collect::<Vec<_>>();
-        let p1 = fields[0];
-        let p2 = fields[10].trim_end_matches('.');
-        let score = match fields[2] {
-            "gain" => fields[3].parse().map_err(|_| Error::InvalidInput),
-            "lose" => fields[3]
-                .parse()
-                .map(|x: i32| -x)
-                .map_err(|_| Error::InvalidInput),
-            _ => Err(Error::InvalidInput),
-        }?;
+    for relation in parse_relations(input) {
+        let (p1, p2, score) = relation?;
+        relations.add_edge(people.intern(p1), people.intern(p2), score);
+    }
 
-        relations.insert((p1, p2), score);
+    Ok(relations)
+}
 
-        people.insert(p1);
-        people.insert(p2);
+#[derive(FromLine)]
+#[line("{person} would {change} {amount} happiness units by sitting next to {neighbor}.")]
+struct Relation<'a> {
+    person: &'a str,
+    change: Change,
+    amount: i32,
+    neighbor: &'a str,
+}
+
+#[derive(Clone)]
+enum Change {
+    Gain,
+    Lose,
+}
+
+impl<'a> Token<'a> for Change {
+    fn read(cursor: &mut Cursor<'a>) -> Result<Self> {
+        cursor.one_of(&[("gain", Change::Gain), ("lose", Change::Lose)])
     }
+}
+
+fn parse_relations(input: &str) -> impl Iterator<Item = Result<(&str, &str, i32)>> {
+    parse::records(input).map(|relation| {
+        let Relation { person, change, amount, neighbor } = relation?;
+        let score = match change {
+            Change::Gain => Some(amount),
+            Change::Lose => amount.checked_neg(),
+        };
+
+        Ok((person, neighbor, score.ok_or(Error::InvalidInput)?))
+    })
+}
+
+// Someone who does not mind where they sit, and whom nobody minds either.
+fn with_me(mut relations: Graph<i32>) -> Graph<i32> {
+    let me = relations.add_node();
+    for p in 0..me {
+        relations.add_edge(me, p, 0);
+        relations.add_edge(p, me, 0);
+    }
+
+    relations
+}
 
-    Ok((people.into_iter().collect(), relations))
+// Sitting next to each other changes the happiness of both people, so the
+// table is a cycle through a graph where each edge adds up both changes.
+fn best_arrangement_score(relations: &Graph<i32>) -> Result<i32> {
+    seating_table(relations)?.best_hamiltonian_cycle(i32::max)
 }
 
-fn best_arrangement_score(people: &[&str], relations: &HashMap<(&str, &str), i32>) -> Result<i32> {
-    let perms = permutations(&people);
+fn seating_table(relations: &Graph<i32>) -> Result<Graph<i32>> {
+    if relations.is_empty() {
+        return Err(Error::InvalidInput);
+    }
+
+    let mut table = Graph::undirected();
+    for _ in 0..relations.len() {
+        table.add_node();
+    }
 
-    let mut result = happiness(&perms[0], &relations)?;
-    for perm in perms.into_iter().skip(1) {
-        let score = happiness(&perm, &relations)?;
-        if score > result {
-            result = score;
+    for p1 in 0..relations.len() {
+        for p2 in p1 + 1..relations.len() {
+            let score = match (relations.weight(p1, p2), relations.weight(p2, p1)) {
+                (Some(s1), Some(s2)) => s1.checked_add(s2).ok_or(Error::InvalidInput)?,
+                _ => return Err(Error::InvalidInput),
+            };
+            table.add_edge(p1, p2, score);
         }
     }
 
-    Ok(result)
+    Ok(table)
 }
 
-// TODO: this logic was copypasted from day 09. Remove duplicated code
-fn permutations<'a, T: ?Sized>(items: &[&'a T]) -> Vec<Vec<&'a T>> {
-    let mut items = items.iter().map(|i| *i).collect::<Vec<_>>();
-    let count = items.len();
+// People seated so far around the table, starting from person 0, as a
+// bitmask, and who sat down last. The table is closed once the last person
+// sits next to person 0.
+struct Seating<'a> {
+    table: &'a Graph<i32>,
+    last: usize,
+    seated: u32,
+    closed: bool,
+}
+
+impl SearchState for Seating<'_> {
+    type Cost = i32;
+
+    fn successors(&self) -> Vec<(Self, i32)> {
+        if self.closed {
+            return vec![];
+        }
+
+        if self.seated.count_ones() as usize == self.table.len() {
+            let back = self.table.weight(self.last, 0);
+            return back.map(|score| (Seating { closed: true, ..*self }, score)).into_iter().collect();
+        }
 
-    if count == 0 {
-        return vec![];
+        self.table
+            .neighbors(self.last)
+            .filter(|&(p, _)| self.seated & (1 << p) == 0)
+            .map(|(p, score)| (Seating { last: p, seated: self.seated | (1 << p), ..*self }, score))
+            .collect()
     }
 
-    if count == 1 {
-        let elem = items[0].clone();
-        return vec![vec![elem]];
+    fn is_goal(&self) -> bool {
+        self.closed
+    }
+}
+
+// Scores can be negative, so every arrangement has to be tried.
+fn best_arrangement_search(relations: &Graph<i32>) -> Result<i32> {
+    let table = seating_table(relations)?;
+    if table.len() > u32::BITS as usize {
+        return Err(Error::InvalidInput);
     }
 
-    let mut result = vec![];
-    for idx in (0..count).rev() {
-        let last = items[count - 1].clone();
+    let start = Seating { table: &table, last: 0, seated: 1, closed: false };
+    let (found, _) = search::exhaustive(start, &mut Unvisited, i32::max);
 
-        let mut perms = permutations(&mut items[0..count - 1]);
-        while let Some(mut perm) = perms.pop() {
-            perm.push(last.clone());
-            result.push(perm);
+    found.map(|(_, score)| score).ok_or(Error::ResultNotFound)
+}
+
+// Pairs of neighbors around the table, in seating order.
+fn neighbors(seats: &[usize]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let count = if seats.len() > 1 { seats.len() } else { 0 };
+    (0..count).map(move |i| (seats[i], seats[(i + 1) % seats.len()]))
+}
+
+// Trying every arrangement is only quick enough for small tables.
+const MAX_VISUALIZED: usize = 10;
+const IMAGE_SIDE: usize = 240;
+
+/// The table every time a happier arrangement is found, with the people as
+/// white dots around it. Neighbors are joined in green when they make each
+/// other happier and in red when they do not.
+impl Visualize for Solution {
+    fn visualize(&self, input: &str, sink: &mut dyn FrameSink) -> Result<()> {
+        let table = seating_table(&parse_input(input)?)?;
+        if table.len() > MAX_VISUALIZED {
+            return Err(Error::InvalidInput);
         }
 
-        items.swap(count - 1, idx);
-    }
+        let mut best = None;
+        let mut arrangements = permutations((1..table.len()).collect());
+        while let Some(others) = arrangements.next_permutation() {
+            let seats = [0].iter().chain(others).copied().collect::<Vec<_>>();
+            let score = neighbors(&seats).try_fold(0i32, |total, (p1, p2)| {
+                total.checked_add(table.weight(p1, p2).ok_or(Error::InvalidInput)?).ok_or(Error::InvalidInput)
+            })?;
 
-    result
+            if best.is_none_or(|b| score > b) {
+                best = Some(score);
+                sink.frame(&Frame::Color(draw_table(&table, &seats)))?;
+            }
+        }
+
+        Ok(())
+    }
 }
 
-fn happiness(arrangement: &[&str], relations: &HashMap<(&str, &str), i32>) -> Result<i32> {
-    let mut result = 0;
-    for i in 0..arrangement.len() - 1 {
-        result += relations
-            .get(&(arrangement[i], arrangement[i + 1]))
-            .ok_or(Error::InvalidInput)?;
-        result += relations
-            .get(&(arrangement[i + 1], arrangement[i]))
-            .ok_or(Error::InvalidInput)?;
+fn draw_table(table: &Graph<i32>, seats: &[usize]) -> Grid<Rgb> {
+    let mut image = Grid::new(IMAGE_SIDE, IMAGE_SIDE, Rgb::BLACK);
+
+    let center = IMAGE_SIDE as f64 / 2.0;
+    let seat = |i: usize| {
+        let angle = std::f64::consts::TAU * i as f64 / seats.len() as f64 - std::f64::consts::FRAC_PI_2;
+        ((center + center * 0.75 * angle.cos()) as i64, (center + center * 0.75 * angle.sin()) as i64)
+    };
+
+    let pairs = neighbors(seats).collect::<Vec<_>>();
+    let strongest = pairs.iter().filter_map(|&(p1, p2)| table.weight(p1, p2)).map(i32::unsigned_abs).max();
+
+    for (i, &(p1, p2)) in pairs.iter().enumerate() {
+        let score = table.weight(p1, p2).unwrap_or(0);
+        let strength = score.unsigned_abs() as f64 / strongest.unwrap_or(1).max(1) as f64;
+        let color = if score >= 0 { Rgb::GREEN } else { Rgb::RED };
+        viz::draw_line(&mut image, seat(i), seat((i + 1) % seats.len()), Rgb::gray(64).mix(color, strength));
+    }
+
+    for i in 0..seats.len() {
+        viz::fill_circle(&mut image, seat(i), 5, Rgb::WHITE);
     }
 
-    result += relations
-        .get(&(arrangement[arrangement.len() - 1], arrangement[0]))
-        .ok_or(Error::InvalidInput)?;
-    result += relations
-        .get(&(arrangement[0], arrangement[arrangement.len() - 1]))
-        .ok_or(Error::InvalidInput)?;
+    image
+}
+
+/// `size` is the number of people, with a happiness change for every
+/// ordered pair of them.
+impl InputGenerator for Solution {
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let people = unique_names(rng, size.max(2));
+
+        let mut lines = vec![];
+        for p1 in people.iter() {
+            for p2 in people.iter().filter(|p| *p != p1) {
+                let change = rng.range(-100, 100);
+                lines.push(format!(
+                    "{} would {} {} happiness units by sitting next to {}.",
+                    p1,
+                    if change < 0 { "lose" } else { "gain" },
+                    change.abs(),
+                    p2));
+            }
+        }
 
-    Ok(result)
+        lines.join("\n")
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::aoc::test::{test_case, Part};
+    use crate::aoc::crosscheck::{cross_check, Config};
+    use crate::aoc::test::{fuzz_corpus_case, generated_case, parse_error_case, test_case, validate_case, Part};
 
     use super::*;
 
@@ -160,4 +311,66 @@ mod test {
 
         test_case(Part::Two, Solution, input, 286);
     }
+
+    #[test]
+    fn generated_input_part2() {
+        generated_case(Part::Two, Solution, 5);
+    }
+
+    #[test]
+    fn cross_check_part1() {
+        let config = Config { max_size: 6, ..Config::default() };
+        let result = cross_check(&Solution, &Solution.part1_implementations(), &config);
+        assert!(result.is_ok(), "{}", result.unwrap_err());
+    }
+
+    #[test]
+    fn cross_check_part2() {
+        let config = Config { max_size: 5, ..Config::default() };
+        let result = cross_check(&Solution, &Solution.part2_implementations(), &config);
+        assert!(result.is_ok(), "{}", result.unwrap_err());
+    }
+
+    #[test]
+    fn fuzz_corpus() {
+        fuzz_corpus_case(Solution, "y2015_day13");
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let input = "
+            Alice would gain 54 happiness units by sitting next to Bob.
+            Bob would win 83 happiness units by sitting next to Alice.
+        ";
+        parse_error_case(Part::One, Solution, input, 3, 23);
+    }
+
+    #[test]
+    fn validate_reports_every_bad_line() {
+        let input = "
+            Alice would gain 54 happiness units by sitting next to Bob.
+            Bob would win 83 happiness units by sitting next to Alice.
+            Bob would lose x happiness units by sitting next to Carol.
+        ";
+        validate_case(Solution, input, &[3, 4]);
+    }
+
+    #[test]
+    fn frame_per_better_arrangement() {
+        let input = "
+            Alice would gain 54 happiness units by sitting next to Bob.
+            Alice would lose 79 happiness units by sitting next to Carol.
+            Bob would gain 83 happiness units by sitting next to Alice.
+            Bob would lose 7 happiness units by sitting next to Carol.
+            Carol would lose 62 happiness units by sitting next to Alice.
+            Carol would gain 60 happiness units by sitting next to Bob.
+        ";
+
+        let mut frames = vec![];
+        Solution.visualize(input, &mut frames).unwrap();
+
+        // both arrangements of three people around a table are the same
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].pixel((IMAGE_SIDE / 2, IMAGE_SIDE / 8)), Rgb::WHITE);
+    }
 }
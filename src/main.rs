@@ -1,48 +1,536 @@
+// A build without any year leaves the registries below with nothing to
+// register.
+#![cfg_attr(not(feature = "y2015"), allow(unused))]
+
 use advent_of_code_rust::aoc;
-use advent_of_code_rust::aoc::{y2015, DaySolution};
+use advent_of_code_rust::aoc::crosscheck::{cross_check, Alternatives, Config};
+use advent_of_code_rust::aoc::debug::{Debugger, Simulate};
+use advent_of_code_rust::aoc::generator::InputGenerator;
+use advent_of_code_rust::aoc::registry;
+use advent_of_code_rust::aoc::rng::Rng;
+use advent_of_code_rust::aoc::stream::StreamingSolution;
+use advent_of_code_rust::aoc::viz::{FrameFiles, FrameSink, Terminal, Visualize};
+#[cfg(feature = "y2015")]
+use advent_of_code_rust::aoc::y2015;
+use advent_of_code_rust::aoc::{DaySolution, Validate};
 use std::env;
 use std::error::Error;
 use std::fmt::Display;
+use std::fs::{self, File};
 use std::io;
-use std::io::Read;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
 use std::str::FromStr;
+use std::time::Duration;
+
+// Years with solutions, and whether their cargo feature compiled them into
+// this build.
+const YEARS: [(u16, bool); 1] = [(2015, cfg!(feature = "y2015"))];
+
+// Days with a cargo feature of their own, for the dependencies only they
+// use, and whether it is enabled.
+const DAYS: [(u16, u8, bool); 2] = [
+    (2015, 4, cfg!(feature = "y2015-day04")),
+    (2015, 12, cfg!(feature = "y2015-day12")),
+];
+
+// Inputs bigger than this are streamed by the days that support it, instead
+// of being read into memory first.
+const STREAMING_THRESHOLD: u64 = 64 << 20;
+
+// `EMBEDDED_INPUTS`, the inputs built into the binary by year and day. It
+// is empty without the `embed-inputs` feature.
+include!(concat!(env!("OUT_DIR"), "/inputs.rs"));
+
+type Solver<'a> = Box<dyn Fn(&str) -> Result<Box<dyn Display>, aoc::Error> + 'a>;
+type StreamSolver<'a> = Box<dyn Fn(&mut dyn BufRead) -> Result<Box<dyn Display>, aoc::Error> + 'a>;
+
+fn main() {
+    // errors are shown with Display, parse errors point at the bad input
+    if let Err(e) = run() {
+        eprintln!("Error: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1).peekable();
+
+    match args.peek().map(String::as_str) {
+        Some("gen") => {
+            args.next();
+            return generate(args);
+        }
+        Some("check") => {
+            args.next();
+            return check(args);
+        }
+        Some("run-all") => {
+            args.next();
+            return run_all(args);
+        }
+        Some("validate") => {
+            args.next();
+            return validate(args);
+        }
+        Some("debug") => {
+            args.next();
+            return debug(args);
+        }
+        _ => (),
+    }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut args = env::args().skip(1);
     let year: u16 = args.next().ok_or("invalid year".to_string())?.parse()?;
     let day: u8 = args.next().ok_or("invalid day".to_string())?.parse()?;
     let part: Part = args.next().ok_or("invalid part".to_string())?.parse()?;
 
-    let solution = get_solution(year, day, part).ok_or("invalid problem".to_string())?;
+    let solution = get_solution(year, day, part).ok_or_else(|| not_found(year, day, "invalid problem"))?;
+
+    let options = run_options(args)?;
+    let visualizer = match &options.viz {
+        Some(_) => Some(get_visualizer(year, day).ok_or_else(|| not_found(year, day, "no visualization for this problem"))?),
+        None => None,
+    };
+
+    let solve = || -> Result<Box<dyn Display>, Box<dyn Error>> {
+        let input = match read_input(year, day, part, visualizer.is_none())? {
+            Input::Text(input) => input,
+            Input::Streamed(result) => return Ok(result),
+        };
+
+        let result = aoc::panic::catch(|| solution(&input))?;
+        if let (Some(visualizer), Some(target)) = (visualizer, &options.viz) {
+            visualize(visualizer, &input, target)?;
+        }
 
-    let mut input = String::new();
-    io::stdin().read_to_string(&mut input)?;
+        Ok(result)
+    };
 
-    let result = solution(&input)?;
+    let result = match options.trace {
+        None => solve()?,
+        Some(path) => {
+            if !aoc::trace::ENABLED {
+                return Err("tracing needs a build with `--features trace`".into());
+            }
+
+            let mut out: Box<dyn Write> = match path {
+                Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+                None => Box::new(io::stderr()),
+            };
+
+            // events are written as they come, so they are there even if the
+            // solution never finishes
+            aoc::trace::with_sink(
+                move |event| {
+                    let _ = writeln!(out, "{}", event);
+                },
+                solve,
+            )?
+        }
+    };
 
     show_result(&result);
     Ok(())
 }
 
-fn get_solution(
-    year: u16,
-    day: u8,
-    part: Part,
-) -> Option<Box<dyn Fn(&str) -> Result<Box<dyn Display>, aoc::Error>>> {
+#[derive(Default)]
+struct RunOptions {
+    trace: Option<Option<PathBuf>>,
+    viz: Option<PathBuf>,
+}
+
+// `--trace` prints the events of the solution to stderr, `--trace <path>`
+// saves them to a file. `--viz <dir>` saves the frames of the visualization
+// as images in a directory, `--viz -` draws them in the terminal.
+fn run_options(args: impl Iterator<Item = String>) -> Result<RunOptions, Box<dyn Error>> {
+    let mut args = args.peekable();
+    let mut options = RunOptions::default();
+
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--trace" => {
+                let path = args.next_if(|a| !a.starts_with("--"));
+                options.trace = Some(path.map(PathBuf::from));
+            }
+            "--viz" => {
+                let target = args.next().ok_or(format!("missing value for '{}'", flag))?;
+                options.viz = Some(target.into());
+            }
+            _ => return Err(format!("invalid option '{}'", flag).into()),
+        }
+    }
+
+    Ok(options)
+}
+
+enum Input {
+    Text(String),
+    Streamed(Box<dyn Display>),
+}
+
+// Big inputs are solved while they are read when the day supports it and
+// `streaming` allows it, otherwise the whole input is returned.
+fn read_input(year: u16, day: u8, part: Part, streaming: bool) -> Result<Input, Box<dyn Error>> {
+    let mut stdin = io::stdin().lock();
+    let mut start = vec![];
+    (&mut stdin).take(STREAMING_THRESHOLD + 1).read_to_end(&mut start)?;
+
+    if streaming && start.len() as u64 > STREAMING_THRESHOLD {
+        if let Some(solution) = get_streaming_solution(year, day, part) {
+            let mut input = BufReader::new(io::Cursor::new(start).chain(stdin));
+            return Ok(Input::Streamed(aoc::panic::catch(|| solution(&mut input))?));
+        }
+    }
+
+    stdin.read_to_end(&mut start)?;
+    Ok(Input::Text(String::from_utf8(start)?))
+}
+
+fn visualize(visualizer: &dyn Visualize, input: &str, target: &Path) -> Result<(), Box<dyn Error>> {
+    let mut sink: Box<dyn FrameSink> = if target == Path::new("-") {
+        Box::new(Terminal::new(io::stderr(), 80, Duration::from_millis(50)))
+    } else {
+        fs::create_dir_all(target)?;
+        Box::new(FrameFiles::new(target))
+    };
+
+    aoc::panic::catch(|| visualizer.visualize(input, sink.as_mut()))?;
+    Ok(())
+}
+
+fn validate(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let year: u16 = args.next().ok_or("invalid year".to_string())?.parse()?;
+    let day: u8 = args.next().ok_or("invalid day".to_string())?.parse()?;
+
+    let validator = get_validator(year, day).ok_or_else(|| not_found(year, day, "invalid problem"))?;
+
+    let input = match args.next() {
+        Some(path) => fs::read_to_string(path)?,
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            input
+        }
+    };
+
+    validator.validate(&input)?;
+
+    println!("input is valid");
+    Ok(())
+}
+
+// The input comes from a file, as the commands are read from stdin.
+fn debug(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let year: u16 = args.next().ok_or("invalid year".to_string())?.parse()?;
+    let day: u8 = args.next().ok_or("invalid day".to_string())?.parse()?;
+    let path = args.next().ok_or("missing input file".to_string())?;
+
+    let simulator = get_simulator(year, day).ok_or_else(|| not_found(year, day, "no simulation for this problem"))?;
+    let input = fs::read_to_string(path)?;
+
+    let mut debugger = Debugger::new(simulator.simulate(&input)?);
+    aoc::debug::repl(&mut debugger, &mut io::stdin().lock(), &mut io::stdout())?;
+
+    Ok(())
+}
+
+fn run_all(args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let mut inputs = PathBuf::from("inputs");
+    for (flag, value) in options(args)? {
+        match flag.as_str() {
+            "--inputs" => inputs = value.into(),
+            _ => return Err(format!("invalid option '{}'", flag).into()),
+        }
+    }
+
+    let mut failed = 0;
+    println!("{:<6}{:<5}{:<6}result", "year", "day", "part");
+
+    for (year, _) in YEARS.into_iter().filter(|&(_, compiled)| compiled) {
+        for day in 1..=25 {
+            let path = inputs.join(year.to_string()).join(format!("{:02}.txt", day));
+            let input = match fs::read_to_string(&path) {
+                Ok(input) => Ok(Some(input)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(embedded_input(year, day).map(str::to_owned)),
+                Err(e) => Err(e),
+            };
+
+            for (part, name) in [(Part::One, 1), (Part::Two, 2)] {
+                let solution = match get_solution(year, day, part) {
+                    Some(s) => s,
+                    None => {
+                        if let Some(feature) = missing_day_feature(year, day) {
+                            println!("{:<6}{:<5}{:<6}not compiled in, build with `--features {}`", year, day, name, feature);
+                        }
+                        continue;
+                    }
+                };
+
+                let row = match &input {
+                    Ok(None) => format!("missing input {}", path.display()),
+                    Err(e) => {
+                        failed += 1;
+                        format!("FAILED: could not read {}: {}", path.display(), e)
+                    }
+                    Ok(Some(input)) => match aoc::panic::catch(|| solution(input)) {
+                        Ok(result) => result.to_string(),
+                        Err(e) => {
+                            failed += 1;
+                            format!("FAILED: {}", e)
+                        }
+                    },
+                };
+
+                println!("{:<6}{:<5}{:<6}{}", year, day, name, row);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} solutions failed", failed).into());
+    }
+
+    Ok(())
+}
+
+// Inputs found on disk are used before the embedded ones, which are only
+// looked at when there is no file at all.
+fn embedded_input(year: u16, day: u8) -> Option<&'static str> {
+    EMBEDDED_INPUTS.iter().find(|&&(y, d, _)| (y, d) == (year, day)).map(|&(_, _, input)| input)
+}
+
+fn generate(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let year: u16 = args.next().ok_or("invalid year".to_string())?.parse()?;
+    let day: u8 = args.next().ok_or("invalid day".to_string())?.parse()?;
+
+    let mut size = 1_000;
+    let mut seed = 0;
+    for (flag, value) in options(args)? {
+        match flag.as_str() {
+            "--size" => size = value.parse()?,
+            "--seed" => seed = value.parse()?,
+            _ => return Err(format!("invalid option '{}'", flag).into()),
+        }
+    }
+
+    let generator = get_generator(year, day).ok_or_else(|| not_found(year, day, "invalid problem"))?;
+
+    println!("{}", generator.generate(&mut Rng::new(seed), size));
+    Ok(())
+}
+
+fn check(mut args: impl Iterator<Item = String>) -> Result<(), Box<dyn Error>> {
+    let year: u16 = args.next().ok_or("invalid year".to_string())?.parse()?;
+    let day: u8 = args.next().ok_or("invalid day".to_string())?.parse()?;
+
+    let mut config = Config::default();
+    for (flag, value) in options(args)? {
+        match flag.as_str() {
+            "--cases" => config.cases = value.parse()?,
+            "--seed" => config.seed = value.parse()?,
+            "--size" => config.max_size = value.parse()?,
+            _ => return Err(format!("invalid option '{}'", flag).into()),
+        }
+    }
+
+    match (year, day) {
+        #[cfg(feature = "y2015")]
+        (2015, 6) => check_alternatives(&y2015::day06::Solution, &config),
+        #[cfg(feature = "y2015")]
+        (2015, 9) => check_alternatives(&y2015::day09::Solution, &config),
+        #[cfg(feature = "y2015")]
+        (2015, 13) => check_alternatives(&y2015::day13::Solution, &config),
+        _ => Err(not_found(year, day, "no alternative implementations for this problem")),
+    }
+}
+
+fn check_alternatives<T, S>(solution: &S, config: &Config) -> Result<(), Box<dyn Error>>
+where
+    T: PartialEq + Display,
+    S: Alternatives<T> + InputGenerator,
+{
+    let parts = [
+        (1, solution.part1_implementations()),
+        (2, solution.part2_implementations()),
+    ];
+
+    for (part, implementations) in parts {
+        let cases = cross_check(solution, &implementations, config)
+            .map_err(|d| format!("part {}: {}", part, d))?;
+
+        println!("part {}: {} implementations agree on {} inputs", part, implementations.len(), cases);
+    }
+
+    Ok(())
+}
+
+// The error for a problem this build has nothing for, telling apart years
+// and days that were left out of it.
+fn not_found(year: u16, day: u8, message: &str) -> Box<dyn Error> {
+    if let Some((_, false)) = YEARS.iter().find(|&&(y, _)| y == year) {
+        return format!("{} was not compiled in, build with `--features y{}`", year, year).into();
+    }
+
+    match missing_day_feature(year, day) {
+        Some(feature) => format!("{} day {} was not compiled in, build with `--features {}`", year, day, feature).into(),
+        None => message.into(),
+    }
+}
+
+// The feature a day needs when this build left it out.
+fn missing_day_feature(year: u16, day: u8) -> Option<String> {
+    DAYS.iter()
+        .any(|&(y, d, compiled)| (y, d) == (year, day) && !compiled)
+        .then(|| format!("y{}-day{:02}", year, day))
+}
+
+fn options(mut args: impl Iterator<Item = String>) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let mut result = vec![];
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(format!("missing value for '{}'", flag))?;
+        result.push((flag, value));
+    }
+
+    Ok(result)
+}
+
+fn get_generator(year: u16, day: u8) -> Option<&'static dyn InputGenerator> {
+    match (year, day) {
+        #[cfg(feature = "y2015")]
+        (2015, 1) => Some(&y2015::day01::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 2) => Some(&y2015::day02::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 3) => Some(&y2015::day03::Solution),
+        #[cfg(feature = "y2015-day04")]
+        (2015, 4) => Some(&y2015::day04::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 5) => Some(&y2015::day05::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 6) => Some(&y2015::day06::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 7) => Some(&y2015::day07::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 8) => Some(&y2015::day08::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 9) => Some(&y2015::day09::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 10) => Some(&y2015::day10::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 11) => Some(&y2015::day11::Solution),
+        #[cfg(feature = "y2015-day12")]
+        (2015, 12) => Some(&y2015::day12::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 13) => Some(&y2015::day13::Solution),
+        _ => None,
+    }
+}
+
+fn get_validator(year: u16, day: u8) -> Option<&'static dyn Validate> {
+    match (year, day) {
+        #[cfg(feature = "y2015")]
+        (2015, 1) => Some(&y2015::day01::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 2) => Some(&y2015::day02::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 3) => Some(&y2015::day03::Solution),
+        #[cfg(feature = "y2015-day04")]
+        (2015, 4) => Some(&y2015::day04::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 5) => Some(&y2015::day05::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 6) => Some(&y2015::day06::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 7) => Some(&y2015::day07::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 8) => Some(&y2015::day08::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 9) => Some(&y2015::day09::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 10) => Some(&y2015::day10::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 11) => Some(&y2015::day11::Solution),
+        #[cfg(feature = "y2015-day12")]
+        (2015, 12) => Some(&y2015::day12::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 13) => Some(&y2015::day13::Solution),
+        _ => None,
+    }
+}
+
+fn get_simulator(year: u16, day: u8) -> Option<&'static dyn Simulate> {
+    match (year, day) {
+        #[cfg(feature = "y2015")]
+        (2015, 1) => Some(&y2015::day01::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 3) => Some(&y2015::day03::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 6) => Some(&y2015::day06::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 7) => Some(&y2015::day07::Solution),
+        _ => None,
+    }
+}
+
+fn get_visualizer(year: u16, day: u8) -> Option<&'static dyn Visualize> {
+    match (year, day) {
+        #[cfg(feature = "y2015")]
+        (2015, 3) => Some(&y2015::day03::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 6) => Some(&y2015::day06::Solution),
+        #[cfg(feature = "y2015")]
+        (2015, 13) => Some(&y2015::day13::Solution),
+        _ => None,
+    }
+}
+
+fn get_solution(year: u16, day: u8, part: Part) -> Option<Solver<'static>> {
     match (year, day) {
+        #[cfg(feature = "y2015")]
         (2015, 1) => Some(transform(&y2015::day01::Solution, part)),
-        (2015, 2) => Some(transform(&y2015::day02::Solution, part)),
+        #[cfg(feature = "y2015")]
         (2015, 3) => Some(transform(&y2015::day03::Solution, part)),
+        #[cfg(feature = "y2015-day04")]
         (2015, 4) => Some(transform(&y2015::day04::Solution, part)),
+        #[cfg(feature = "y2015")]
         (2015, 5) => Some(transform(&y2015::day05::Solution, part)),
+        #[cfg(feature = "y2015")]
         (2015, 6) => Some(transform(&y2015::day06::Solution, part)),
+        #[cfg(feature = "y2015")]
         (2015, 7) => Some(transform(&y2015::day07::Solution, part)),
+        #[cfg(feature = "y2015")]
         (2015, 8) => Some(transform(&y2015::day08::Solution, part)),
+        #[cfg(feature = "y2015")]
         (2015, 9) => Some(transform(&y2015::day09::Solution, part)),
-        (2015, 10) => Some(transform(&y2015::day10::Solution, part)),
+        #[cfg(feature = "y2015")]
         (2015, 11) => Some(transform(&y2015::day11::Solution, part)),
+        #[cfg(feature = "y2015-day12")]
         (2015, 12) => Some(transform(&y2015::day12::Solution, part)),
+        #[cfg(feature = "y2015")]
         (2015, 13) => Some(transform(&y2015::day13::Solution, part)),
+        _ => registered(year, day, part),
+    }
+}
+
+// Days declared with `#[aoc]` instead of a `DaySolution`.
+fn registered(year: u16, day: u8, part: Part) -> Option<Solver<'static>> {
+    let part = match part {
+        Part::One => 1,
+        Part::Two => 2,
+    };
+
+    registry::find(year, day, part).map(|entry| Box::new(entry.solve) as Solver)
+}
+
+fn get_streaming_solution(year: u16, day: u8, part: Part) -> Option<StreamSolver<'static>> {
+    match (year, day) {
+        #[cfg(feature = "y2015")]
+        (2015, 1) => Some(transform_streaming(&y2015::day01::Solution, part)),
+        #[cfg(feature = "y2015")]
+        (2015, 3) => Some(transform_streaming(&y2015::day03::Solution, part)),
+        #[cfg(feature = "y2015")]
+        (2015, 6) => Some(transform_streaming(&y2015::day06::Solution, part)),
         _ => None,
     }
 }
@@ -50,7 +538,7 @@ fn get_solution(
 fn transform<T>(
     day_solution: &dyn DaySolution<T>,
     part: Part,
-) -> Box<dyn Fn(&str) -> Result<Box<dyn Display>, aoc::Error> + '_>
+) -> Solver<'_>
 where
     T: Display + 'static,
 {
@@ -66,10 +554,30 @@ where
     }
 }
 
+fn transform_streaming<T>(
+    streaming_solution: &dyn StreamingSolution<T>,
+    part: Part,
+) -> StreamSolver<'_>
+where
+    T: Display + 'static,
+{
+    match part {
+        Part::One => Box::new(|r| match streaming_solution.stream_part1(r) {
+            Ok(r) => Ok(Box::new(r)),
+            Err(e) => Err(e),
+        }),
+        Part::Two => Box::new(|r| match streaming_solution.stream_part2(r) {
+            Ok(r) => Ok(Box::new(r)),
+            Err(e) => Err(e),
+        }),
+    }
+}
+
 fn show_result(result: &dyn Display) {
-    println!("{}", result.to_string());
+    println!("{}", result);
 }
 
+#[derive(Clone, Copy)]
 enum Part {
     One,
     Two,